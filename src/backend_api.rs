@@ -5,6 +5,7 @@ use url::Url;
 
 
 use crate::config::Config;
+use crate::handler::{PostInfo, PostRevisionSummary};
 
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Ord, Eq, Hash)]
@@ -51,4 +52,46 @@ impl BackendApi {
         let bytes = response.bytes().await?;
         Ok(bytes.to_vec())
     }
+
+    /// Like `get_bytes`, but `None` if not found, and an error on other error statuses.
+    async fn get_bytes_if_found(&self, path: &str, query: HashMap<String, String>) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let url = self.get_url(path, &query)?;
+        let response = reqwest::get(&url).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let bytes = response.error_for_status()?.bytes().await?;
+        Ok(Some(bytes.to_vec()))
+    }
+
+    /// The post, or `None` if there is no such post.
+    pub async fn get_post(&self, post_uuid: &str) -> Result<Option<PostInfo>, anyhow::Error> {
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), post_uuid.to_string());
+        match self.get_bytes_if_found("post/info", query).await? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Ok(None),
+        }
+    }
+
+    /// Revisions of the post, or `None` if there is no such post.
+    pub async fn get_post_revisions(&self, post_uuid: &str) -> Result<Option<Vec<PostRevisionSummary>>, anyhow::Error> {
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), post_uuid.to_string());
+        match self.get_bytes_if_found("post/revisions", query).await? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Ok(None),
+        }
+    }
+
+    /// The post as of the revision, or `None` if the post has no such revision.
+    pub async fn get_post_revision(&self, post_uuid: &str, revision_uuid: &str) -> Result<Option<PostInfo>, anyhow::Error> {
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), post_uuid.to_string());
+        query.insert("revision_uuid".to_string(), revision_uuid.to_string());
+        match self.get_bytes_if_found("post/revision", query).await? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Ok(None),
+        }
+    }
 }
@@ -5,18 +5,24 @@ use std::str::FromStr;
 use std::path::PathBuf;
 
 use axum::{
-    http::Request,
+    http::{Request, StatusCode, header},
     routing::{get, post},
     Router,
-    response::Response,
+    response::{IntoResponse, Response},
     middleware::Next, body::Body,
 };
 use tower_http::services::ServeDir;
 
 use alarkhabil_frontend::handler;
+use alarkhabil_frontend::search;
+use alarkhabil_frontend::views;
+use alarkhabil_frontend::config;
+use alarkhabil_frontend::redirect;
+use alarkhabil_frontend::pages;
+use alarkhabil_frontend::navigation;
 
 
-static RESPONSE_HEADER_CSP: &str = "default-src 'self'; img-src 'self' data: blob:; connect-src 'self' http: https:; base-uri 'none'; form-action 'none'; frame-ancestors 'none';";
+static RESPONSE_HEADER_CSP: &str = "default-src 'self'; img-src 'self' data: blob:; connect-src 'self' http: https:; base-uri 'none'; form-action 'self'; frame-ancestors 'none';";
 static RESPONSE_HEADER_X_FRAME_OPTIONS: &str = "DENY";
 static RESPONSE_HEADER_X_CONTENT_TYPE_OPTIONS: &str = "nosniff";
 
@@ -35,6 +41,16 @@ async fn add_global_headers(req: Request<Body>, next: Next) -> Response {
     res
 }
 
+/// Middleware to redirect legacy URLs, before any route is handled.
+async fn redirect_legacy_urls(req: Request<Body>, next: Next) -> Response {
+    let config = config::load_config().await;
+    if let Some(redirect) = redirect::find_redirect(&config.redirects, req.uri().path(), req.uri().query()) {
+        let status = StatusCode::from_u16(redirect.rule.status).unwrap_or(StatusCode::MOVED_PERMANENTLY);
+        return (status, [(header::LOCATION, redirect.location)]).into_response();
+    }
+    next.run(req).await
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenvy::dotenv()?;
@@ -50,7 +66,7 @@ async fn main() -> anyhow::Result<()> {
         "branding-default"
     };
 
-    // define routes
+    // define routes; `routes::ROUTES` must list them, as its tests check
     let app = Router::new()
         // top page
         .route("/", get(handler::handler_root))
@@ -65,6 +81,36 @@ async fn main() -> anyhow::Result<()> {
 
         // post
         .route("/c/:channel_handle/:post_uuid/", get(handler::handler_post))
+        .route("/c/:channel_handle/:post_uuid/revisions/", get(handler::handler_post_revisions))
+        .route("/c/:channel_handle/:post_uuid/r/:revision_uuid/", get(handler::handler_post_revision))
+        .route("/c/:channel_handle/:post_uuid/diff/", get(handler::handler_post_diff))
+        .route("/c/:channel_handle/:post_uuid/index.md", get(handler::handler_post_markdown))
+        .route("/c/:channel_handle/:post_uuid/index.txt", get(handler::handler_post_plain_text))
+        .route("/c/:channel_handle/:post_uuid/cite.bib", get(handler::handler_post_bibtex))
+        .route("/c/:channel_handle/:post_uuid/cite.ris", get(handler::handler_post_ris))
+        .route("/c/:channel_handle/:post_uuid/cite.json", get(handler::handler_post_csl_json))
+
+        // archives
+        .route("/archive/", get(handler::handler_archive))
+        .route("/archive/:year/", get(handler::handler_archive))
+        .route("/archive/:year/:month/", get(handler::handler_archive))
+        .route("/c/:channel_handle/archive/", get(handler::handler_channel_archive))
+        .route("/c/:channel_handle/archive/:year/", get(handler::handler_channel_archive))
+        .route("/c/:channel_handle/archive/:year/:month/", get(handler::handler_channel_archive))
+
+        // statistics
+        .route("/c/:channel_handle/stats/", get(handler::handler_channel_stats))
+        .route("/authors/:author_uuid/stats/", get(handler::handler_author_stats))
+
+        // EPUB exports
+        .route("/c/:channel_handle/export.epub", get(handler::handler_channel_epub))
+        .route("/authors/:author_uuid/export.epub", get(handler::handler_author_epub))
+
+        // series
+        .route("/series/:series_name/", get(handler::handler_series))
+
+        // languages
+        .route("/lang/:lang_code/", get(handler::handler_lang))
 
         // authors
         .route("/authors/", get(handler::handler_author_list))
@@ -72,7 +118,12 @@ async fn main() -> anyhow::Result<()> {
 
         // tags
         .route("/tags/", get(handler::handler_tag_list))
-        .route("/tags/:tag_name/", get(handler::handler_tag))
+        .route("/tags/:tag_names/", get(handler::handler_tag))
+        .route("/tags/:tag_names/*excluded_tags", get(handler::handler_tag))
+
+        // search
+        .route("/search/", get(handler::handler_search))
+        .route("/opensearch.xml", get(handler::handler_opensearch))
 
         // JavaScript required pages
         .route("/invites/", get(handler::handler_javascript_required))
@@ -87,14 +138,32 @@ async fn main() -> anyhow::Result<()> {
         // frontend api
         .route("/frontend/api/v1/markdown/parse", post(handler::api_v1_markdown_parse))
         .route("/frontend/api/v1/config/get", get(handler::api_v1_config_get))
-        .route("/frontend/api/v1/timestamp/format", get(handler::api_v1_timestamp_format))
+        .route("/frontend/api/v1/timestamp/format", get(handler::api_v1_timestamp_format).post(handler::api_v1_timestamp_format_batch))
+        .route("/frontend/api/v1/views/record", post(handler::api_v1_views_record))
+        .route("/frontend/api/v1/views/get", get(handler::api_v1_views_get))
+
+        // local pages, vanity routes of meta pages, or 404 page
+        .fallback(handler::handler_fallback)
 
-        // 404 page
-        .fallback(handler::handler_404)
+        // redirect legacy urls
+        .layer(axum::middleware::from_fn(redirect_legacy_urls))
 
         // add global headers
         .layer(axum::middleware::from_fn(add_global_headers));
 
+    // keep the search index up to date
+    tokio::spawn(search::run_indexer());
+
+    // write out view counts
+    tokio::spawn(views::run_flusher());
+
+    // keep navigation links from the backend up to date
+    tokio::spawn(navigation::run_refresher());
+
+    // keep local pages in sync with the content directory
+    pages::reload_if_changed(&pages::content_dir());
+    tokio::spawn(pages::run_watcher());
+
     // run server
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     let server = axum::serve(listener, app);
@@ -0,0 +1,57 @@
+
+//! Tests which redirect rule in the config matches each given path.
+//! Usage: alarkhabil-redirect-test <path>...
+//! The config is read from `CONFIG_FILE` (default: config.json).
+
+use std::env;
+use std::process::ExitCode;
+
+use alarkhabil_frontend::config;
+use alarkhabil_frontend::redirect;
+
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() || paths.iter().any(|path| path == "-h" || path == "--help") {
+        eprintln!("Usage: alarkhabil-redirect-test <path>...");
+        return ExitCode::from(2);
+    }
+
+    let _ = dotenvy::dotenv();
+    let config = config::load_config().await;
+    for (index, rule) in config.redirects.iter().enumerate() {
+        if let Err(e) = redirect::validate(rule) {
+            eprintln!("warning: rule #{} ({:?} {}) is ignored: {}", index, rule.kind, rule.from, e);
+        }
+    }
+
+    let mut all_matched = true;
+    for original in &paths {
+        let (path, query) = match original.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (original.as_str(), None),
+        };
+        match redirect::find_redirect(&config.redirects, path, query) {
+            Some(redirect) => println!(
+                "{} -> {} {} (rule #{}: {:?} {})",
+                original,
+                redirect.rule.status,
+                redirect.location,
+                redirect.index,
+                redirect.rule.kind,
+                redirect.rule.from,
+            ),
+            None => {
+                println!("{} -> no match", original);
+                all_matched = false;
+            },
+        }
+    }
+
+    if all_matched {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,43 @@
+//! Small in-memory caches of backend results.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+
+/// Values by string key, each expiring a fixed time after insertion.
+/// When full, expired entries are dropped first, then everything.
+#[derive(Debug)]
+pub struct TtlCache<V> {
+    ttl: Duration,
+    capacity: usize,
+    entries: Mutex<HashMap<String, (Instant, V)>>,
+}
+
+impl<V: Clone> TtlCache<V> {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<V> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(key)
+            .filter(|(created, _)| created.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn insert(&self, key: &str, value: V) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity {
+            entries.retain(|_, (created, _)| created.elapsed() < self.ttl);
+        }
+        if entries.len() >= self.capacity {
+            entries.clear();
+        }
+        entries.insert(key.to_string(), (Instant::now(), value));
+    }
+}
@@ -0,0 +1,96 @@
+//! Non-Gregorian calendars shown next to Gregorian dates.
+
+use chrono::{NaiveDate, Datelike};
+
+use serde::{Serialize, Deserialize};
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Calendar {
+    Gregorian,
+    Japanese, // 和暦 (era names), from 1873 when Japan adopted the Gregorian calendar
+    Islamic, // tabular (arithmetical) Hijri calendar
+}
+
+impl Calendar {
+    /// Formats the date, or returns `None` for Gregorian and unsupported dates.
+    pub fn format_date(&self, date: NaiveDate) -> Option<String> {
+        match self {
+            Calendar::Gregorian => None,
+            Calendar::Japanese => format_japanese(date),
+            Calendar::Islamic => Some(format_islamic(date)),
+        }
+    }
+}
+
+
+/// (era name, first day of the era)
+const JAPANESE_ERAS: &[(&str, (i32, u32, u32))] = &[
+    ("令和", (2019, 5, 1)),
+    ("平成", (1989, 1, 8)),
+    ("昭和", (1926, 12, 25)),
+    ("大正", (1912, 7, 30)),
+    ("明治", (1868, 10, 23)),
+];
+
+fn format_japanese(date: NaiveDate) -> Option<String> {
+    if date < NaiveDate::from_ymd_opt(1873, 1, 1)? {
+        return None; // lunisolar calendar
+    }
+
+    let (era, start) = JAPANESE_ERAS.iter()
+        .map(|&(era, (y, m, d))| (era, NaiveDate::from_ymd_opt(y, m, d).unwrap()))
+        .find(|&(_, start)| date >= start)?;
+    let era_year = date.year() - start.year() + 1;
+    let era_year = if era_year == 1 {
+        "元".to_string()
+    } else {
+        era_year.to_string()
+    };
+
+    Some(format!("{}{}年{}月{}日", era, era_year, date.month(), date.day()))
+}
+
+
+const ISLAMIC_MONTHS: [&str; 12] = [
+    "محرم", "صفر", "ربيع الأول", "ربيع الآخر", "جمادى الأولى", "جمادى الآخرة",
+    "رجب", "شعبان", "رمضان", "شوال", "ذو القعدة", "ذو الحجة",
+];
+
+/// Fixed day number (1 = 0001-01-01 Gregorian) of 1 Muharram AH 1.
+const ISLAMIC_EPOCH: i64 = 227015;
+
+fn fixed_from_islamic(year: i64, month: i64, day: i64) -> i64 {
+    day + 29 * (month - 1) + (6 * month - 1).div_euclid(11)
+        + (year - 1) * 354 + (3 + 11 * year).div_euclid(30)
+        + ISLAMIC_EPOCH - 1
+}
+
+/// Year, month (1-12) and day in the tabular Islamic calendar.
+/// May differ by a day or so from calendars based on moon sighting.
+pub fn islamic_from_date(date: NaiveDate) -> (i64, u32, u32) {
+    let fixed = date.num_days_from_ce() as i64;
+    let year = (30 * (fixed - ISLAMIC_EPOCH) + 10646).div_euclid(10631);
+    let prior_days = fixed - fixed_from_islamic(year, 1, 1);
+    let month = (11 * prior_days + 330).div_euclid(325);
+    let day = fixed - fixed_from_islamic(year, month, 1) + 1;
+    (year, month as u32, day as u32)
+}
+
+fn format_islamic(date: NaiveDate) -> String {
+    let (year, month, day) = islamic_from_date(date);
+    format!(
+        "{} {} {} هـ",
+        arabic_indic_digits(&day.to_string()),
+        ISLAMIC_MONTHS[month as usize - 1],
+        arabic_indic_digits(&year.to_string()),
+    )
+}
+
+fn arabic_indic_digits(s: &str) -> String {
+    s.chars().map(|c| match c.to_digit(10) {
+        Some(d) => char::from_u32(0x0660 + d).unwrap(),
+        None => c,
+    }).collect()
+}
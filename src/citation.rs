@@ -0,0 +1,217 @@
+//! Citations of posts in BibTeX, RIS and CSL-JSON, and as preformatted text.
+
+use chrono::Datelike;
+
+use serde::Serialize;
+
+use url::Url;
+
+use crate::config::Config;
+use crate::handler::PostInfo;
+use crate::unix_time::UnixTime;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CitationFormat {
+    BibTex,
+    Ris,
+    CslJson,
+}
+
+impl CitationFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            CitationFormat::BibTex => "application/x-bibtex; charset=utf-8",
+            CitationFormat::Ris => "application/x-research-info-systems; charset=utf-8",
+            CitationFormat::CslJson => "application/vnd.citationstyles.csl+json; charset=utf-8",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CitationFormat::BibTex => "bib",
+            CitationFormat::Ris => "ris",
+            CitationFormat::CslJson => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Citation {
+    pub key: String, // ASCII, unique per post
+    pub title: String,
+    pub author: String,
+    pub container_title: String, // channel name
+    pub publisher: String, // site name
+    pub lang: String,
+    pub tags: Vec<String>,
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub url: String, // permalink
+    pub revision_uuid: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CslName {
+    literal: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CslDate {
+    #[serde(rename = "date-parts")]
+    date_parts: Vec<Vec<i32>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CslItem {
+    id: String,
+    #[serde(rename = "type")]
+    item_type: String,
+    title: String,
+    author: Vec<CslName>,
+    #[serde(rename = "container-title")]
+    container_title: String,
+    publisher: String,
+    language: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    keyword: String,
+    issued: CslDate,
+    #[serde(rename = "URL")]
+    url: String,
+    version: String,
+}
+
+/// Escapes characters special in BibTeX field values.
+fn escape_bibtex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '{' | '}' | '%' | '&' | '$' | '#' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            },
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// RIS values are one line each.
+fn ris_value(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+impl Citation {
+    /// Citation of the current revision of the post, dated in server_timezone.
+    pub fn new(post: &PostInfo, config: &Config) -> Result<Self, anyhow::Error> {
+        let date = UnixTime::new(post.revision_date).to_datetime(config.server_timezone()).date_naive();
+        let url = Url::parse(&config.top_url)?.join(&post.url())?.to_string();
+        let uuid_prefix: String = post.post_uuid.chars().filter(|c| c.is_ascii_alphanumeric()).take(8).collect();
+        Ok(Self {
+            key: format!("{}{}-{}", post.channel.handle, date.year(), uuid_prefix),
+            title: post.title.clone(),
+            author: post.author.name.clone(),
+            container_title: post.channel.name.clone(),
+            publisher: config.site_name.clone(),
+            lang: post.channel.lang.clone(),
+            tags: post.tags.clone(),
+            year: date.year(),
+            month: date.month(),
+            day: date.day(),
+            url,
+            revision_uuid: post.revision_uuid.clone(),
+        })
+    }
+
+    fn date(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Author (YYYY-MM-DD). Title. Channel, Site. URL
+    pub fn to_text(&self) -> String {
+        format!(
+            "{} ({}). {}. {}, {}. {}",
+            self.author,
+            self.date(),
+            self.title,
+            self.container_title,
+            self.publisher,
+            self.url,
+        )
+    }
+
+    pub fn to_bibtex(&self) -> String {
+        let fields = [
+            // braced again, so that the display name is kept whole rather than split into first and last names
+            ("author", format!("{{{{{}}}}}", escape_bibtex(&self.author))),
+            ("title", format!("{{{}}}", escape_bibtex(&self.title))),
+            ("howpublished", format!("{{{}}}", escape_bibtex(&self.container_title))),
+            ("publisher", format!("{{{}}}", escape_bibtex(&self.publisher))),
+            ("year", format!("{{{}}}", self.year)),
+            ("month", format!("{{{}}}", self.month)),
+            ("date", format!("{{{}}}", self.date())),
+            ("url", format!("{{{}}}", self.url)),
+            ("langid", format!("{{{}}}", escape_bibtex(&self.lang))),
+            ("keywords", format!("{{{}}}", escape_bibtex(&self.tags.join(", ")))),
+            ("note", format!("{{Revision {}}}", escape_bibtex(&self.revision_uuid))),
+        ];
+        let mut bibtex = format!("@misc{{{},\n", self.key);
+        for (name, value) in fields {
+            bibtex.push_str(&format!("  {} = {},\n", name, value));
+        }
+        bibtex.push_str("}\n");
+        bibtex
+    }
+
+    pub fn to_ris(&self) -> String {
+        let mut lines = vec![
+            ("TY", "BLOG".to_string()),
+            ("TI", ris_value(&self.title)),
+            ("AU", ris_value(&self.author)),
+            ("T2", ris_value(&self.container_title)),
+            ("PB", ris_value(&self.publisher)),
+            ("PY", self.year.to_string()),
+            ("DA", format!("{:04}/{:02}/{:02}", self.year, self.month, self.day)),
+            ("UR", self.url.clone()),
+            ("LA", ris_value(&self.lang)),
+        ];
+        for tag in &self.tags {
+            lines.push(("KW", ris_value(tag)));
+        }
+        lines.push(("N1", format!("Revision {}", ris_value(&self.revision_uuid))));
+        lines.push(("ER", String::new()));
+        lines.iter().map(|(tag, value)| format!("{}  - {}\r\n", tag, value)).collect()
+    }
+
+    pub fn to_csl_json(&self) -> Result<String, anyhow::Error> {
+        let item = CslItem {
+            id: self.key.clone(),
+            item_type: "post-weblog".to_string(),
+            title: self.title.clone(),
+            author: vec![CslName { literal: self.author.clone() }],
+            container_title: self.container_title.clone(),
+            publisher: self.publisher.clone(),
+            language: self.lang.clone(),
+            keyword: self.tags.join(", "),
+            issued: CslDate {
+                date_parts: vec![vec![self.year, self.month as i32, self.day as i32]],
+            },
+            url: self.url.clone(),
+            version: self.revision_uuid.clone(),
+        };
+        Ok(serde_json::to_string_pretty(&vec![item])?)
+    }
+
+    pub fn format(&self, format: CitationFormat) -> Result<String, anyhow::Error> {
+        Ok(match format {
+            CitationFormat::BibTex => self.to_bibtex(),
+            CitationFormat::Ris => self.to_ris(),
+            CitationFormat::CslJson => self.to_csl_json()?,
+        })
+    }
+}
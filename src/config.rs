@@ -1,8 +1,9 @@
 //! This whole scheme fails when the default configuration is broken on compile time.
 
 
+use std::collections::BTreeMap;
 use std::env;
-use std::sync::OnceLock;
+use std::sync::{Mutex, OnceLock};
 
 use tokio::fs;
 
@@ -11,8 +12,160 @@ use chrono_tz::Tz;
 use serde::{Serialize, Deserialize};
 
 use crate::template::NavigationItem;
+use crate::calendar::Calendar;
+use crate::lang::lang_matches;
+use crate::unix_time::UnixTime;
+use crate::markdown;
+use crate::routes;
 
 
+/// A series of posts listed explicitly, in reading order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesDefinition {
+    pub name: String, // used in /series/:name/
+    pub title: String,
+    pub posts: Vec<String>, // post uuids
+}
+
+/// A section of the top page. Titles default to one for the kind of section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HomeSection {
+    /// Posts listed explicitly, in order.
+    Featured {
+        title: Option<String>,
+        posts: Vec<String>, // post uuids
+    },
+
+    Latest {
+        title: Option<String>,
+        limit: Option<usize>,
+    },
+
+    /// Most viewed posts in the last week.
+    Popular {
+        limit: Option<usize>,
+    },
+
+    /// Latest posts of a channel.
+    Channel {
+        title: Option<String>,
+        handle: String,
+        limit: Option<usize>,
+    },
+
+    /// Latest posts with a tag.
+    Tag {
+        title: Option<String>,
+        tag_name: String,
+        limit: Option<usize>,
+    },
+
+    ChannelDirectory {
+        title: Option<String>,
+    },
+
+    /// Rendered content of a meta page.
+    MetaPage {
+        page_name: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnouncementSeverity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+impl AnnouncementSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnouncementSeverity::Info => "info",
+            AnnouncementSeverity::Warning => "warning",
+            AnnouncementSeverity::Critical => "critical",
+        }
+    }
+
+    /// Only critical announcements interrupt screen readers.
+    pub fn aria_role(&self) -> &'static str {
+        match self {
+            AnnouncementSeverity::Critical => "alert",
+            _ => "status",
+        }
+    }
+}
+
+/// A site-wide banner shown between the start and the end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Announcement {
+    pub text: String, // Markdown
+
+    #[serde(default)]
+    pub severity: AnnouncementSeverity,
+
+    #[serde(default)]
+    pub start: String, // in server_timezone unless with an offset, e.g. "2024-04-01 09:00"; empty for no start
+
+    #[serde(default)]
+    pub end: String, // exclusive; empty for no end
+
+    #[serde(default)]
+    pub routes: Vec<String>, // path prefixes like "/c/"; empty for every page
+
+    // filled when the config is loaded, for the JS side
+
+    #[serde(default, skip_deserializing)]
+    pub start_timestamp: Option<u64>,
+
+    #[serde(default, skip_deserializing)]
+    pub end_timestamp: Option<u64>,
+
+    #[serde(default, skip_deserializing)]
+    pub html: String,
+}
+
+impl Announcement {
+    pub fn is_active(&self, path: &str, now: UnixTime) -> bool {
+        let now = u64::from(now);
+        self.start_timestamp.map(|start| start <= now).unwrap_or(true)
+            && self.end_timestamp.map(|end| now < end).unwrap_or(true)
+            && (self.routes.is_empty() || self.routes.iter().any(|prefix| path.starts_with(prefix.as_str())))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedirectKind {
+    /// The whole path equals `from`.
+    Exact,
+
+    /// The path starts with `from` at a segment boundary; the rest is appended to `to`.
+    Prefix,
+
+    /// The path matches the regex `from`; `$1` or `${name}` in `to` are replaced with captures.
+    Regex,
+}
+
+/// Redirect from a legacy or renamed URL. The first matching rule wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedirectRule {
+    pub kind: RedirectKind,
+    pub from: String,
+    pub to: String, // path or absolute url
+
+    #[serde(default = "RedirectRule::default_status")]
+    pub status: u16, // 301 or 302
+}
+
+impl RedirectRule {
+    pub fn default_status() -> u16 {
+        301
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default = "Config::default_api_url")]
@@ -21,6 +174,9 @@ pub struct Config {
     #[serde(default = "Config::default_site_name")]
     pub site_name: String, // also used for OGP
 
+    #[serde(default = "Config::default_site_lang")]
+    pub site_lang: String, // BCP 47 language tag of the site chrome
+
     #[serde(default = "Config::default_site_description")]
     pub site_description: String,
 
@@ -41,6 +197,36 @@ pub struct Config {
 
     #[serde(default = "Config::default_server_timezone")]
     pub server_timezone: String,
+
+    #[serde(default = "Config::default_secondary_calendars")]
+    pub secondary_calendars: BTreeMap<String, Calendar>, // language range (or "*") -> calendar
+
+    #[serde(default = "Config::default_search_refresh_interval")]
+    pub search_refresh_interval: u64, // seconds between search index refreshes
+
+    #[serde(default = "Config::default_related_posts_count")]
+    pub related_posts_count: usize, // 0 to disable
+
+    #[serde(default = "Config::default_tag_list_min_page_count")]
+    pub tag_list_min_page_count: u64, // tags with fewer posts are hidden from /tags/ by default
+
+    #[serde(default = "Config::default_series_tag_prefix")]
+    pub series_tag_prefix: String, // tags like "series:name" define series; empty to disable
+
+    #[serde(default = "Config::default_series")]
+    pub series: Vec<SeriesDefinition>,
+
+    #[serde(default = "Config::default_home_sections")]
+    pub home_sections: Vec<HomeSection>, // top page, in order
+
+    #[serde(default = "Config::default_announcements")]
+    pub announcements: Vec<Announcement>,
+
+    #[serde(default = "Config::default_redirects")]
+    pub redirects: Vec<RedirectRule>,
+
+    #[serde(default = "Config::default_meta_page_routes")]
+    pub meta_page_routes: BTreeMap<String, String>, // path like "/about/" -> meta page name
 }
 
 static CONFIG: OnceLock<Config> = OnceLock::new();
@@ -61,6 +247,10 @@ impl Config {
         Self::default_ref().site_name.clone()
     }
 
+    pub fn default_site_lang() -> String {
+        Self::default_ref().site_lang.clone()
+    }
+
     pub fn default_site_description() -> String {
         Self::default_ref().site_description.clone()
     }
@@ -89,8 +279,129 @@ impl Config {
         Self::default_ref().server_timezone.clone()
     }
 
+    pub fn default_secondary_calendars() -> BTreeMap<String, Calendar> {
+        Self::default_ref().secondary_calendars.clone()
+    }
+
+    pub fn default_search_refresh_interval() -> u64 {
+        Self::default_ref().search_refresh_interval
+    }
+
+    pub fn default_related_posts_count() -> usize {
+        Self::default_ref().related_posts_count
+    }
+
+    pub fn default_tag_list_min_page_count() -> u64 {
+        Self::default_ref().tag_list_min_page_count
+    }
+
+    pub fn default_series_tag_prefix() -> String {
+        Self::default_ref().series_tag_prefix.clone()
+    }
+
+    pub fn default_series() -> Vec<SeriesDefinition> {
+        Self::default_ref().series.clone()
+    }
+
+    pub fn default_home_sections() -> Vec<HomeSection> {
+        Self::default_ref().home_sections.clone()
+    }
+
+    pub fn default_announcements() -> Vec<Announcement> {
+        Self::default_ref().announcements.clone()
+    }
+
+    pub fn default_redirects() -> Vec<RedirectRule> {
+        Self::default_ref().redirects.clone()
+    }
+
+    pub fn default_meta_page_routes() -> BTreeMap<String, String> {
+        Self::default_ref().meta_page_routes.clone()
+    }
+
     pub fn server_timezone(&self) -> Tz {
-        self.server_timezone.parse().unwrap_or_else(|_| Tz::UTC)
+        self.server_timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// Calendar shown next to Gregorian dates for content in the language.
+    /// The most specific matching language range wins.
+    pub fn secondary_calendar(&self, lang: &str) -> Option<Calendar> {
+        self.secondary_calendars.iter()
+            .filter(|(range, _)| lang_matches(range, lang))
+            .max_by_key(|(range, _)| if range.as_str() == "*" { 0 } else { range.len() })
+            .map(|(_, calendar)| *calendar)
+    }
+
+    /// Date in the secondary calendar for the language, or an empty string.
+    pub fn secondary_date_string(&self, lang: &str, date: UnixTime) -> String {
+        self.secondary_calendar(lang)
+            .and_then(|calendar| date.format_calendar(self.server_timezone(), calendar))
+            .unwrap_or_default()
+    }
+}
+
+impl Config {
+    /// Fills the timestamps and HTML of announcements.
+    /// Announcements with unparsable times are dropped.
+    fn resolve_announcements(mut self) -> Self {
+        let timezone = self.server_timezone();
+        let parse = |text: &str| -> Result<Option<u64>, ()> {
+            if text.trim().is_empty() {
+                return Ok(None);
+            }
+            UnixTime::parse_in_timezone(text, timezone).map(|time| Some(u64::from(time))).ok_or(())
+        };
+        self.announcements = self.announcements.into_iter().filter_map(|announcement| {
+            match (parse(&announcement.start), parse(&announcement.end)) {
+                (Ok(start_timestamp), Ok(end_timestamp)) => Some(Announcement {
+                    start_timestamp,
+                    end_timestamp,
+                    html: markdown::to_html(&announcement.text),
+                    ..announcement
+                }),
+                _ => {
+                    log::warn!("Invalid time in announcement: {:?} - {:?}", announcement.start, announcement.end);
+                    None
+                },
+            }
+        }).collect();
+        self
+    }
+
+    /// Drops vanity routes that a route of the server handles, as they would never be reached.
+    fn drop_routed_meta_page_routes(mut self) -> Self {
+        self.meta_page_routes.retain(|route, page_name| {
+            let is_routed = routes::is_routed(route);
+            if is_routed {
+                log::warn!("Vanity route {} of meta page {} is handled by another route, ignored", route, page_name);
+            }
+            !is_routed
+        });
+        self
+    }
+
+    pub fn meta_page_route(&self, page_name: &str) -> Option<&str> {
+        self.meta_page_routes.iter()
+            .find(|(_, name)| name.as_str() == page_name)
+            .map(|(route, _)| route.as_str())
+    }
+
+    /// Vanity route of the meta page, or `/meta/:page_name/`.
+    pub fn meta_page_url(&self, page_name: &str) -> String {
+        self.meta_page_route(page_name)
+            .map(|route| route.to_string())
+            .unwrap_or_else(|| format!("/meta/{}/", page_name))
+    }
+
+    pub fn meta_page_for_route(&self, path: &str) -> Option<&str> {
+        self.meta_page_routes.get(path).map(|name| name.as_str())
+    }
+
+    pub fn active_announcements(&self, path: &str, now: UnixTime) -> Vec<Announcement> {
+        self.announcements.iter()
+            .filter(|announcement| announcement.is_active(path, now))
+            .cloned()
+            .collect()
     }
 }
 
@@ -100,17 +411,27 @@ impl Default for Config {
     }
 }
 
+/// The config in the bytes, with announcements resolved and unreachable vanity routes dropped; the default if the bytes are not a valid config.
+/// The last result is reused while the bytes are unchanged, so that announcements are
+/// rendered and warned about once per change of the file rather than on every request.
+fn parse_config(config_bytes: Vec<u8>) -> Config {
+    static LAST_CONFIG: Mutex<Option<(Vec<u8>, Config)>> = Mutex::new(None);
+    let mut last_config = LAST_CONFIG.lock().unwrap();
+    if let Some((last_bytes, config)) = &*last_config {
+        if *last_bytes == config_bytes {
+            return config.clone();
+        }
+    }
+
+    let config: Config = serde_json::from_slice(&config_bytes).unwrap_or_default();
+    let config = config.resolve_announcements().drop_routed_meta_page_routes();
+    *last_config = Some((config_bytes, config.clone()));
+    config
+}
+
 pub async fn load_config() -> Config {
     let config_path = env::var("CONFIG_FILE").unwrap_or_else(|_| "config.json".to_string());
-    let config_bytes = if let Ok(config_bytes) = fs::read(&config_path).await {
-        config_bytes
-    } else {
-        return Config::default();
-    };
-
-    if let Ok(config) = serde_json::from_slice(&config_bytes) {
-        config
-    } else {
-        Config::default()
-    }
+    // a missing file reads as empty, which is not a valid config
+    let config_bytes: Vec<u8> = fs::read(&config_path).await.unwrap_or_default();
+    parse_config(config_bytes)
 }
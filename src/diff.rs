@@ -0,0 +1,599 @@
+//! Word-level diffs between revisions of Markdown sources, shown as a unified
+//! source diff and as rendered HTML with the changes marked.
+//!
+//! CJK text has no spaces between words, so each CJK character is compared on its own.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::markdown;
+use crate::search::{escape_html, is_cjk, is_arabic_ignorable};
+
+
+/// Texts further apart than this many edits are treated as entirely replaced.
+const MAX_EDIT_DISTANCE: isize = 1000;
+
+/// Unchanged lines shown around changes in the source diff.
+const CONTEXT_LINES: usize = 3;
+
+// markers of changed runs in the merged source, from the Private Use Area
+const INSERT_START: char = '\u{E000}';
+const INSERT_END: char = '\u{E001}';
+const DELETE_START: char = '\u{E002}';
+const DELETE_END: char = '\u{E003}';
+const MARKERS: [char; 4] = [INSERT_START, INSERT_END, DELETE_START, DELETE_END];
+
+/// Markers as percent-encoded in link destinations.
+const ENCODED_MARKERS: [&str; 4] = ["%EE%80%80", "%EE%80%81", "%EE%80%82", "%EE%80%83"];
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffLine {
+    pub kind: String, // "context", "delete" or "insert"
+    pub marker: String, // " ", "-" or "+"
+    pub old_number: String, // empty for insertions
+    pub new_number: String, // empty for deletions
+    pub html: String, // with changed words in <del> or <ins>
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffHunk {
+    pub header: String, // "@@ -1,4 +1,5 @@"
+    pub lines: Vec<DiffLine>,
+}
+
+fn push_run(runs: &mut Vec<(DiffOp, usize)>, op: DiffOp, len: usize) {
+    if len == 0 {
+        return;
+    }
+    match runs.last_mut() {
+        Some((last_op, last_len)) if *last_op == op => *last_len += len,
+        _ => runs.push((op, len)),
+    }
+}
+
+/// Myers' algorithm, one operation per element, or `None` beyond `MAX_EDIT_DISTANCE`.
+fn myers<T: PartialEq>(a: &[T], b: &[T]) -> Option<Vec<DiffOp>> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = (n + m).min(MAX_EDIT_DISTANCE);
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+
+    // furthest x on each diagonal -d..=d after each step d
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut distance = None;
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[i - 1] < v[i + 1]) { v[i + 1] } else { v[i - 1] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[i] = x;
+        }
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        if (n - m).abs() <= d && v[(n - m + offset) as usize] >= n {
+            distance = Some(d);
+            break;
+        }
+    }
+    let distance = distance?;
+
+    let mut ops = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..=distance).rev() {
+        let previous = &trace[d as usize - 1];
+        let furthest = |k: isize| previous[(k + d - 1) as usize];
+        let k = x - y;
+        let previous_k = if k == -d || (k != d && furthest(k - 1) < furthest(k + 1)) { k + 1 } else { k - 1 };
+        let previous_x = furthest(previous_k);
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            ops.push(DiffOp::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        ops.push(if previous_k == k + 1 { DiffOp::Insert } else { DiffOp::Delete });
+        x = previous_x;
+        y = previous_y;
+    }
+    while x > 0 && y > 0 {
+        ops.push(DiffOp::Equal);
+        x -= 1;
+        y -= 1;
+    }
+    ops.reverse();
+    Some(ops)
+}
+
+/// Shortest edit script from `old` to `new` as runs of operations with their lengths.
+/// Between unchanged runs, deletions come before insertions.
+fn diff_sequences<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(DiffOp, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let ops = myers(a, b).unwrap_or_else(|| {
+        let mut ops = vec![DiffOp::Delete; a.len()];
+        ops.extend(vec![DiffOp::Insert; b.len()]);
+        ops
+    });
+
+    let mut runs = Vec::new();
+    push_run(&mut runs, DiffOp::Equal, prefix);
+    let (mut deleted, mut inserted) = (0, 0);
+    for op in ops {
+        match op {
+            DiffOp::Delete => deleted += 1,
+            DiffOp::Insert => inserted += 1,
+            DiffOp::Equal => {
+                push_run(&mut runs, DiffOp::Delete, std::mem::take(&mut deleted));
+                push_run(&mut runs, DiffOp::Insert, std::mem::take(&mut inserted));
+                push_run(&mut runs, DiffOp::Equal, 1);
+            },
+        }
+    }
+    push_run(&mut runs, DiffOp::Delete, deleted);
+    push_run(&mut runs, DiffOp::Insert, inserted);
+    push_run(&mut runs, DiffOp::Equal, suffix);
+    runs
+}
+
+fn is_word_char(c: char) -> bool {
+    (c.is_alphanumeric() && !is_cjk(c)) || is_arabic_ignorable(c) || ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+fn is_space_char(c: char) -> bool {
+    c.is_whitespace() && c != '\n'
+}
+
+/// Splits the text into words, runs of spaces, and single other characters,
+/// including each CJK character and newline.
+pub fn split_words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let continues: Option<fn(char) -> bool> = if is_word_char(c) {
+            Some(is_word_char)
+        } else if is_space_char(c) {
+            Some(is_space_char)
+        } else {
+            None
+        };
+        if let Some(continues) = continues {
+            while let Some(&(next_start, next)) = chars.peek() {
+                if !continues(next) {
+                    break;
+                }
+                end = next_start + next.len_utf8();
+                chars.next();
+            }
+        }
+        words.push(&text[start..end]);
+    }
+    words
+}
+
+/// Changes from `old` to `new`, word by word. Spaces alone between changes are
+/// included in the changes, so that replaced phrases read as a whole.
+pub fn diff_words(old: &str, new: &str) -> Vec<Change> {
+    let old_words = split_words(old);
+    let new_words = split_words(new);
+
+    let runs = diff_sequences(&old_words, &new_words);
+    let mut changes = Vec::new();
+    let (mut deleted, mut inserted) = (String::new(), String::new());
+    let (mut i, mut j) = (0, 0);
+    for (index, (op, len)) in runs.iter().copied().enumerate() {
+        match op {
+            DiffOp::Delete => {
+                deleted.push_str(&old_words[i..i + len].concat());
+                i += len;
+            },
+            DiffOp::Insert => {
+                inserted.push_str(&new_words[j..j + len].concat());
+                j += len;
+            },
+            DiffOp::Equal => {
+                let text = old_words[i..i + len].concat();
+                i += len;
+                j += len;
+                let is_gap = index > 0 && index + 1 < runs.len() && !text.contains('\n') && text.trim().is_empty();
+                if is_gap {
+                    deleted.push_str(&text);
+                    inserted.push_str(&text);
+                    continue;
+                }
+                flush_changes(&mut changes, &mut deleted, &mut inserted);
+                changes.push(Change { op, text });
+            },
+        }
+    }
+    flush_changes(&mut changes, &mut deleted, &mut inserted);
+    changes
+}
+
+fn flush_changes(changes: &mut Vec<Change>, deleted: &mut String, inserted: &mut String) {
+    if !deleted.is_empty() {
+        changes.push(Change { op: DiffOp::Delete, text: std::mem::take(deleted) });
+    }
+    if !inserted.is_empty() {
+        changes.push(Change { op: DiffOp::Insert, text: std::mem::take(inserted) });
+    }
+}
+
+/// Escaped text of both versions with the changes in `<del>` and `<ins>`.
+pub fn to_inline_html(old: &str, new: &str) -> String {
+    let mut html = String::new();
+    for change in diff_words(old, new) {
+        match change.op {
+            DiffOp::Equal => html.push_str(&escape_html(&change.text)),
+            DiffOp::Delete => html.push_str(&format!("<del>{}</del>", escape_html(&change.text))),
+            DiffOp::Insert => html.push_str(&format!("<ins>{}</ins>", escape_html(&change.text))),
+        }
+    }
+    html
+}
+
+/// Lines of one side of the changes, escaped, with the changed words in the tag.
+fn side_lines(changes: &[Change], side: DiffOp, tag: &str) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for change in changes.iter().filter(|change| change.op == DiffOp::Equal || change.op == side) {
+        for (i, part) in change.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(String::new());
+            }
+            if part.is_empty() {
+                continue;
+            }
+            let line = lines.last_mut().unwrap();
+            if change.op == DiffOp::Equal {
+                line.push_str(&escape_html(part));
+            } else {
+                line.push_str(&format!("<{}>{}</{}>", tag, escape_html(part), tag));
+            }
+        }
+    }
+    lines
+}
+
+/// Unified diff of the lines, with changed words marked within changed lines.
+pub fn unified_hunks(old: &str, new: &str) -> Vec<DiffHunk> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    // (line, old lines before it, new lines before it)
+    let mut lines: Vec<(DiffLine, usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let runs = diff_sequences(&old_lines, &new_lines);
+    let mut index = 0;
+    while index < runs.len() {
+        let (op, len) = runs[index];
+        if op == DiffOp::Equal {
+            for line in &old_lines[i..i + len] {
+                lines.push((DiffLine {
+                    kind: "context".to_string(),
+                    marker: " ".to_string(),
+                    old_number: (i + 1).to_string(),
+                    new_number: (j + 1).to_string(),
+                    html: escape_html(line),
+                }, i, j));
+                i += 1;
+                j += 1;
+            }
+            index += 1;
+            continue;
+        }
+
+        // a deletion, an insertion, or a deletion followed by an insertion
+        let deleted = if op == DiffOp::Delete { len } else { 0 };
+        let inserted = match runs.get(index + if deleted > 0 { 1 } else { 0 }) {
+            Some((DiffOp::Insert, len)) => *len,
+            _ => 0,
+        };
+        let changes = diff_words(&old_lines[i..i + deleted].join("\n"), &new_lines[j..j + inserted].join("\n"));
+        let (delete_start, insert_start) = (i, j);
+        if deleted > 0 {
+            for html in side_lines(&changes, DiffOp::Delete, "del") {
+                lines.push((DiffLine {
+                    kind: "delete".to_string(),
+                    marker: "-".to_string(),
+                    old_number: (i + 1).to_string(),
+                    new_number: String::new(),
+                    html,
+                }, i, insert_start));
+                i += 1;
+            }
+        }
+        if inserted > 0 {
+            for html in side_lines(&changes, DiffOp::Insert, "ins") {
+                lines.push((DiffLine {
+                    kind: "insert".to_string(),
+                    marker: "+".to_string(),
+                    old_number: String::new(),
+                    new_number: (j + 1).to_string(),
+                    html,
+                }, delete_start + deleted, j));
+                j += 1;
+            }
+        }
+        index += if deleted > 0 && inserted > 0 { 2 } else { 1 };
+    }
+
+    // changed lines with their context, merging overlapping ranges
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (index, (line, _, _)) in lines.iter().enumerate() {
+        if line.kind == "context" {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(lines.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges.into_iter().map(|(start, end)| {
+        let hunk_lines = &lines[start..end];
+        let old_count = hunk_lines.iter().filter(|(line, _, _)| line.kind != "insert").count();
+        let new_count = hunk_lines.iter().filter(|(line, _, _)| line.kind != "delete").count();
+        let (_, old_before, new_before) = hunk_lines[0];
+        let old_start = if old_count == 0 { old_before } else { old_before + 1 };
+        let new_start = if new_count == 0 { new_before } else { new_before + 1 };
+        DiffHunk {
+            header: format!("@@ -{},{} +{},{} @@", old_start, old_count, new_start, new_count),
+            lines: hunk_lines.iter().map(|(line, _, _)| line.clone()).collect(),
+        }
+    }).collect()
+}
+
+/// Block markup at the start of a line: indentation, headings, list items and quotes.
+fn block_prefix() -> &'static Regex {
+    static BLOCK_PREFIX: OnceLock<Regex> = OnceLock::new();
+    BLOCK_PREFIX.get_or_init(|| Regex::new(r"^[ \t]*(?:(?:#{1,6}|[-*+]|\d{1,9}[.)]|>)[ \t]+|>)*").unwrap())
+}
+
+/// Lines of markup only, such as fences, thematic breaks and table delimiter rows,
+/// which stop working with markers in them.
+fn is_markup_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+        || (!trimmed.is_empty() && trimmed.chars().all(|c| "-=*_|:~ \t".contains(c)))
+}
+
+/// Appends the changed text to the merged source, wrapped in markers line by line.
+fn push_marked(merged: &mut String, text: &str, start: char, end: char) {
+    for (i, part) in text.split('\n').enumerate() {
+        if i > 0 {
+            merged.push('\n');
+        }
+        let at_line_start = merged.is_empty() || merged.ends_with('\n');
+        let (prefix, body) = if at_line_start {
+            if is_markup_line(part) {
+                (part, "")
+            } else {
+                let prefix_len = block_prefix().find(part).map(|found| found.end()).unwrap_or(0);
+                part.split_at(prefix_len)
+            }
+        } else {
+            ("", part)
+        };
+        merged.push_str(prefix);
+        if !body.is_empty() {
+            merged.push(start);
+            merged.push_str(body);
+            merged.push(end);
+        }
+    }
+}
+
+/// Tag with the deleted text in its attributes, such as link destinations, left out.
+fn clean_tag(tag: &str) -> String {
+    let tag = ENCODED_MARKERS.iter().zip(MARKERS)
+        .fold(tag.to_string(), |tag, (encoded, marker)| tag.replace(encoded, &marker.to_string()));
+    let mut cleaned = String::with_capacity(tag.len());
+    let mut is_deleted = false;
+    for c in tag.chars() {
+        match c {
+            DELETE_START => is_deleted = true,
+            DELETE_END => is_deleted = false,
+            INSERT_START | INSERT_END => {},
+            _ if !is_deleted => cleaned.push(c),
+            _ => {},
+        }
+    }
+    cleaned
+}
+
+/// Turns the markers into `<ins>` and `<del>`. They are closed before every tag and
+/// reopened after it, so that they always nest properly.
+fn replace_markers(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut current: Option<&str> = None;
+    let mut is_open = false;
+    let mut tag: Option<String> = None;
+    for c in html.chars() {
+        if let Some(tag_text) = tag.as_mut() {
+            tag_text.push(c);
+            if c == '>' {
+                output.push_str(&clean_tag(tag_text));
+                tag = None;
+            }
+            continue;
+        }
+        let next = match c {
+            INSERT_START => Some(Some("ins")),
+            DELETE_START => Some(Some("del")),
+            INSERT_END | DELETE_END => Some(None),
+            _ => None,
+        };
+        if next.is_some() || c == '<' {
+            if is_open {
+                output.push_str(&format!("</{}>", current.unwrap_or_default()));
+                is_open = false;
+            }
+            match next {
+                Some(next) => current = next,
+                None => tag = Some(c.to_string()),
+            }
+            continue;
+        }
+        if !is_open {
+            if let Some(name) = current {
+                output.push_str(&format!("<{}>", name));
+                is_open = true;
+            }
+        }
+        output.push(c);
+    }
+    if let Some(tag_text) = tag {
+        output.push_str(&clean_tag(&tag_text));
+    }
+    if is_open {
+        output.push_str(&format!("</{}>", current.unwrap_or_default()));
+    }
+    output
+}
+
+/// Block markup at the start of the line and the rest; markup lines are all prefix.
+fn split_block_prefix(line: &str) -> (&str, &str) {
+    if is_markup_line(line) {
+        return (line, "");
+    }
+    let prefix_len = block_prefix().find(line).map(|found| found.end()).unwrap_or(0);
+    line.split_at(prefix_len)
+}
+
+/// A changed line with the block markup of the new version, unmarked.
+/// When the markup itself changed, as between heading levels, the whole text is marked.
+fn merged_line(old: &str, new: &str) -> String {
+    let (old_prefix, old_body) = split_block_prefix(old);
+    let (new_prefix, new_body) = split_block_prefix(new);
+    let mut line = new_prefix.to_string();
+    if old_prefix != new_prefix {
+        push_marked(&mut line, old_body, DELETE_START, DELETE_END);
+        push_marked(&mut line, new_body, INSERT_START, INSERT_END);
+        return line;
+    }
+    for change in diff_words(old_body, new_body) {
+        match change.op {
+            DiffOp::Equal => line.push_str(&change.text),
+            DiffOp::Delete => push_marked(&mut line, &change.text, DELETE_START, DELETE_END),
+            DiffOp::Insert => push_marked(&mut line, &change.text, INSERT_START, INSERT_END),
+        }
+    }
+    line
+}
+
+/// Renders both versions merged, with insertions in `<ins>` and deletions in `<del>`.
+/// Changed lines are compared word by word when paired one to one, and marked whole otherwise.
+pub fn to_html(old: &str, new: &str) -> String {
+    let old = old.replace(MARKERS, "");
+    let new = new.replace(MARKERS, "");
+    let old_lines: Vec<&str> = old.split('\n').collect();
+    let new_lines: Vec<&str> = new.split('\n').collect();
+
+    let mut merged: Vec<String> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let runs = diff_sequences(&old_lines, &new_lines);
+    let mut index = 0;
+    while index < runs.len() {
+        let (op, len) = runs[index];
+        if op == DiffOp::Equal {
+            merged.extend(new_lines[j..j + len].iter().map(|line| line.to_string()));
+            i += len;
+            j += len;
+            index += 1;
+            continue;
+        }
+
+        // a deletion, an insertion, or a deletion followed by an insertion
+        let deleted = if op == DiffOp::Delete { len } else { 0 };
+        let inserted = match runs.get(index + if deleted > 0 { 1 } else { 0 }) {
+            Some((DiffOp::Insert, len)) => *len,
+            _ => 0,
+        };
+        if deleted == inserted {
+            for (old_line, new_line) in old_lines[i..i + deleted].iter().zip(&new_lines[j..j + inserted]) {
+                merged.push(merged_line(old_line, new_line));
+            }
+        } else {
+            for (lines, start, end) in [(&old_lines[i..i + deleted], DELETE_START, DELETE_END), (&new_lines[j..j + inserted], INSERT_START, INSERT_END)] {
+                for line in lines {
+                    let mut marked = String::new();
+                    push_marked(&mut marked, line, start, end);
+                    merged.push(marked);
+                }
+            }
+        }
+        i += deleted;
+        j += inserted;
+        index += if deleted > 0 && inserted > 0 { 2 } else { 1 };
+    }
+    replace_markers(&markdown::to_html(&merged.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heading_level_change_keeps_the_heading() {
+        let html = to_html("# Title\n", "## Title\n");
+        assert!(html.contains("<h2>"), "{}", html);
+        assert!(!html.contains('#'), "{}", html);
+        assert!(html.contains("<del>Title</del><ins>Title</ins>"), "{}", html);
+    }
+
+    #[test]
+    fn list_items_keep_their_markers() {
+        let html = to_html("- apple pie\n- banana\n", "- apple tart\n- banana\n");
+        assert_eq!(html.matches("<li>").count(), 2, "{}", html);
+        assert!(html.contains("<del>pie</del><ins>tart</ins>"), "{}", html);
+
+        let html = to_html("- one\n", "1. one\n");
+        assert!(html.contains("<ol>"), "{}", html);
+        assert!(!html.contains("1."), "{}", html);
+    }
+
+    #[test]
+    fn fences_stay_unmarked() {
+        let html = to_html("```\nlet a = 1;\n```\n", "```\nlet a = 2;\n```\n");
+        assert!(html.contains("<pre>"), "{}", html);
+        assert!(!html.contains("`"), "{}", html);
+        assert!(html.contains("<del>1</del><ins>2</ins>"), "{}", html);
+    }
+
+    #[test]
+    fn cjk_changes_are_per_character() {
+        let html = to_html("東京都に住む\n", "京都府に住む\n");
+        assert!(html.contains("<del>東</del>京都<ins>府</ins>に住む"), "{}", html);
+    }
+
+    #[test]
+    fn added_lines_are_marked_whole() {
+        let html = to_html("# Title\n", "# Title\n\n> quoted\n");
+        assert!(html.contains("<blockquote>"), "{}", html);
+        assert!(html.contains("<ins>quoted</ins>"), "{}", html);
+    }
+}
@@ -0,0 +1,205 @@
+//! EPUB 3 books of posts, one chapter per post.
+
+use std::io::{Cursor, Write};
+use std::sync::OnceLock;
+
+use askama::Template;
+
+use regex::{Captures, Regex};
+
+use url::Url;
+
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::lang::TextDirection;
+use crate::template::{
+    EpubPackageTemplate,
+    EpubNavTemplate,
+    EpubTitlePageTemplate,
+    EpubChapterTemplate,
+    EpubChapterItem,
+};
+use crate::unix_time::UnixTime;
+
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+const STYLE_CSS: &str = r#"body { margin: 0 5%; line-height: 1.6; }
+h1 { font-size: 1.5em; }
+.chapter-meta, .chapter-source, .title-page-source { font-size: 0.8em; }
+pre { white-space: pre-wrap; }
+img { max-width: 100%; }
+"#;
+
+
+#[derive(Debug, Clone)]
+pub struct EpubChapter {
+    pub title: String,
+    pub byline: String, // author, channel or both
+    pub lang: String,
+    pub date: String,
+    pub date_value: String, // for <time datetime="...">
+    pub url: String, // absolute url of the post
+    pub content_html: String, // from markdown::to_html
+}
+
+#[derive(Debug, Clone)]
+pub struct EpubBook {
+    pub identifier: String, // e.g. urn:uuid:...
+    pub title: String,
+    pub creators: Vec<String>,
+    pub publisher: String,
+    pub description_html: String, // from markdown::to_html, for the title page
+    pub description: String, // plain text, for the metadata
+    pub lang: String,
+    pub url: String, // absolute url of the channel or the author
+    pub modified: UnixTime, // of the latest chapter
+    pub notice: String, // such as that posts are left out, on the title page and in the description
+    pub chapters: Vec<EpubChapter>, // in reading order
+}
+
+fn void_element() -> &'static Regex {
+    static VOID_ELEMENT: OnceLock<Regex> = OnceLock::new();
+    VOID_ELEMENT.get_or_init(|| Regex::new(r"<(area|base|br|col|embed|hr|img|input|link|meta|source|track|wbr)\b([^>]*?)\s*/?>").unwrap())
+}
+
+fn entity() -> &'static Regex {
+    static ENTITY: OnceLock<Regex> = OnceLock::new();
+    ENTITY.get_or_init(|| Regex::new(r"&([A-Za-z][A-Za-z0-9]*);").unwrap())
+}
+
+fn root_relative_url() -> &'static Regex {
+    static ROOT_RELATIVE_URL: OnceLock<Regex> = OnceLock::new();
+    ROOT_RELATIVE_URL.get_or_init(|| Regex::new(r#"\b(href|src)="(/|/[^/"][^"]*)""#).unwrap())
+}
+
+/// Converts HTML from `markdown::to_html` to XHTML: void elements are closed,
+/// entities not defined in XML become numeric, and root-relative urls absolute.
+fn to_xhtml(html: &str, base_url: &Url) -> String {
+    let xhtml = void_element().replace_all(html, "<$1$2 />");
+    let xhtml = entity().replace_all(&xhtml, |captures: &Captures| match &captures[1] {
+        "amp" | "lt" | "gt" | "quot" | "apos" => captures[0].to_string(),
+        "nbsp" => "&#160;".to_string(),
+        name => format!("&amp;{};", name),
+    });
+    let xhtml = root_relative_url().replace_all(&xhtml, |captures: &Captures| {
+        match base_url.join(&captures[2]) {
+            Ok(url) => format!("{}=\"{}\"", &captures[1], url),
+            Err(_) => captures[0].to_string(),
+        }
+    });
+    xhtml.into_owned()
+}
+
+/// `dir` attribute value, or empty if unknown.
+fn dir_attribute(lang: &str, text: &str) -> String {
+    match TextDirection::detect(lang, text) {
+        TextDirection::Auto => String::new(),
+        dir => dir.to_string(),
+    }
+}
+
+impl EpubBook {
+    fn lang(&self) -> &str {
+        if self.lang.is_empty() { "und" } else { &self.lang }
+    }
+
+    fn is_rtl(&self) -> bool {
+        TextDirection::detect(&self.lang, &self.title) == TextDirection::Rtl
+    }
+
+    /// The book as an EPUB container.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, anyhow::Error> {
+        let base_url = Url::parse(&self.url)?;
+        let lang = self.lang().to_string();
+        let dir = dir_attribute(&self.lang, &self.title);
+
+        let mut chapter_files = Vec::new();
+        for (i, chapter) in self.chapters.iter().enumerate() {
+            let chapter_lang = if chapter.lang.is_empty() { lang.clone() } else { chapter.lang.clone() };
+            let content_html = to_xhtml(&chapter.content_html, &base_url);
+            let has_remote_resources = content_html.contains("src=\"http");
+            let template = EpubChapterTemplate {
+                title: chapter.title.clone(),
+                title_dir: TextDirection::from_text(&chapter.title).map(|dir| dir.to_string()).unwrap_or_default(),
+                byline: chapter.byline.clone(),
+                lang: chapter_lang.clone(),
+                dir: dir_attribute(&chapter_lang, &chapter.title),
+                date: chapter.date.clone(),
+                date_value: chapter.date_value.clone(),
+                url: chapter.url.clone(),
+                content_html,
+            };
+            let item = EpubChapterItem {
+                id: format!("chapter-{}", i + 1),
+                file_name: format!("chapter-{:04}.xhtml", i + 1),
+                title: chapter.title.clone(),
+                has_remote_resources,
+            };
+            chapter_files.push((item, template.render()?));
+        }
+        let chapters: Vec<EpubChapterItem> = chapter_files.iter().map(|(item, _)| item.clone()).collect();
+
+        let package = EpubPackageTemplate {
+            identifier: self.identifier.clone(),
+            title: self.title.clone(),
+            creators: self.creators.clone(),
+            publisher: self.publisher.clone(),
+            description: [self.description.trim(), self.notice.as_str()].iter()
+                .filter(|text| !text.is_empty())
+                .copied()
+                .collect::<Vec<&str>>()
+                .join(" "),
+            lang: lang.clone(),
+            dir: dir.clone(),
+            url: self.url.clone(),
+            date: self.modified.to_datetime(chrono_tz::UTC).format("%Y-%m-%d").to_string(),
+            modified: self.modified.to_datetime(chrono_tz::UTC).format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            is_rtl: self.is_rtl(),
+            chapters: chapters.clone(),
+        };
+        let nav = EpubNavTemplate {
+            title: self.title.clone(),
+            lang: lang.clone(),
+            dir: dir.clone(),
+            chapters,
+        };
+        let title_page = EpubTitlePageTemplate {
+            title: self.title.clone(),
+            creators: self.creators.join(", "),
+            publisher: self.publisher.clone(),
+            lang,
+            dir,
+            url: self.url.clone(),
+            description_html: to_xhtml(&self.description_html, &base_url),
+            notice: self.notice.clone(),
+        };
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        // the mimetype comes first and uncompressed, as required
+        writer.start_file("mimetype", FileOptions::default().compression_method(CompressionMethod::Stored))?;
+        writer.write_all(b"application/epub+zip")?;
+
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let files = [
+            ("META-INF/container.xml".to_string(), CONTAINER_XML.to_string()),
+            ("OEBPS/content.opf".to_string(), package.render()?),
+            ("OEBPS/nav.xhtml".to_string(), nav.render()?),
+            ("OEBPS/title.xhtml".to_string(), title_page.render()?),
+            ("OEBPS/style.css".to_string(), STYLE_CSS.to_string()),
+        ];
+        let chapter_files = chapter_files.into_iter().map(|(item, xhtml)| (format!("OEBPS/{}", item.file_name), xhtml));
+        for (path, content) in files.into_iter().chain(chapter_files) {
+            writer.start_file(path, options)?;
+            writer.write_all(content.as_bytes())?;
+        }
+        Ok(writer.finish()?.into_inner())
+    }
+}
@@ -0,0 +1,133 @@
+//! Front matter of Markdown files: the small subset of YAML with one `key: value` per line.
+//! Values are plain, single-quoted or double-quoted strings; written values are
+//! JSON strings and arrays, which are also valid YAML.
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+
+fn parse_value(text: &str) -> Result<String, anyhow::Error> {
+    if let Some(quoted) = text.strip_prefix('"') {
+        // the first quote not escaped by a backslash closes the string
+        let mut is_escaped = false;
+        let end = quoted.char_indices()
+            .find(|(_, c)| {
+                let closes = *c == '"' && !is_escaped;
+                is_escaped = *c == '\\' && !is_escaped;
+                closes
+            })
+            .map(|(i, _)| i + 1)
+            .ok_or_else(|| anyhow::anyhow!("Unterminated string: {}", text))?;
+        let rest = text[end + 1..].trim();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return Err(anyhow::anyhow!("Unexpected text after string: {}", text));
+        }
+        return Ok(serde_json::from_str(&text[..=end])?);
+    }
+    if let Some(quoted) = text.strip_prefix('\'') {
+        let mut value = String::new();
+        let mut chars = quoted.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\'' {
+                value.push(c);
+            } else if chars.peek() == Some(&'\'') {
+                chars.next();
+                value.push('\'');
+            } else {
+                let rest = chars.collect::<String>();
+                let rest = rest.trim();
+                if !rest.is_empty() && !rest.starts_with('#') {
+                    return Err(anyhow::anyhow!("Unexpected text after string: {}", text));
+                }
+                return Ok(value);
+            }
+        }
+        return Err(anyhow::anyhow!("Unterminated string: {}", text));
+    }
+    let value = match text.find(" #") {
+        Some(comment) => &text[..comment],
+        None => text,
+    };
+    Ok(value.trim().to_string())
+}
+
+/// Parses the front matter, without the `---` lines, into the fields of `T`.
+pub fn from_str<T: DeserializeOwned>(text: &str) -> Result<T, anyhow::Error> {
+    let mut fields = Map::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let (key, value) = line.split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("Expected key: value, got {}", line))?;
+        let key = key.trim();
+        if key.is_empty() || line.starts_with(char::is_whitespace) {
+            return Err(anyhow::anyhow!("Expected key: value, got {}", line));
+        }
+        if fields.insert(key.to_string(), Value::String(parse_value(value.trim())?)).is_some() {
+            return Err(anyhow::anyhow!("Duplicate key {}", key));
+        }
+    }
+    Ok(serde_json::from_value(Value::Object(fields))?)
+}
+
+/// Writes the fields in order, one per line. Values are expected to be strings or lists of strings.
+pub fn to_string(fields: &[(&str, Value)]) -> String {
+    fields.iter()
+        .map(|(key, value)| format!("{}: {}\n", key, value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn parses_plain_and_quoted_values() {
+        let fields: BTreeMap<String, String> = from_str(concat!(
+            "# comment\n",
+            "title: Colophon # trailing comment\n",
+            "date: \"2024-04-01\"\n",
+            "path: '/it''s/'\n",
+            "\n",
+            "note: \"a: \\\"b\\\" \\u00e9\"\n",
+        )).unwrap();
+        assert_eq!(fields["title"], "Colophon");
+        assert_eq!(fields["date"], "2024-04-01");
+        assert_eq!(fields["path"], "/it's/");
+        assert_eq!(fields["note"], "a: \"b\" é");
+    }
+
+    #[test]
+    fn quoted_value_ends_at_its_closing_quote() {
+        let fields: BTreeMap<String, String> = from_str(concat!(
+            "title: \"Colophon\" # the \"about\" page\n",
+            "path: \"/a\\\\\" # ends after the escaped backslash\n",
+        )).unwrap();
+        assert_eq!(fields["title"], "Colophon");
+        assert_eq!(fields["path"], "/a\\");
+        assert!(from_str::<BTreeMap<String, String>>("title: \"a\" b\n").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert!(from_str::<BTreeMap<String, String>>("title Colophon\n").is_err());
+        assert!(from_str::<BTreeMap<String, String>>("title: \"open\n").is_err());
+        assert!(from_str::<BTreeMap<String, String>>("title: a\ntitle: b\n").is_err());
+        assert!(from_str::<BTreeMap<String, String>>("  nested: a\n").is_err());
+    }
+
+    #[test]
+    fn written_values_read_back() {
+        let title = "Title: with \"quotes\" # and ' more";
+        let text = to_string(&[
+            ("title", Value::from(title)),
+            ("tags", Value::from(vec!["a", "b"])),
+        ]);
+        assert_eq!(text, "title: \"Title: with \\\"quotes\\\" # and ' more\"\ntags: [\"a\",\"b\"]\n");
+        let read: BTreeMap<String, String> = from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(read["title"], title);
+    }
+}
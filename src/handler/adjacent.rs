@@ -0,0 +1,70 @@
+//! Older and newer posts within a channel.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::backend_api::BackendApi;
+use crate::cache::TtlCache;
+
+use super::{PostInfo, PostSummary};
+
+
+/// New posts show up in the navigation of older ones after at most this time.
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+const CACHE_CAPACITY: usize = 256;
+
+
+// channel uuid -> posts of the channel, newest first
+static CHANNEL_POSTS_CACHE: OnceLock<TtlCache<Arc<Vec<PostSummary>>>> = OnceLock::new();
+
+fn cache() -> &'static TtlCache<Arc<Vec<PostSummary>>> {
+    CHANNEL_POSTS_CACHE.get_or_init(|| TtlCache::new(CACHE_TTL, CACHE_CAPACITY))
+}
+
+async fn fetch_channel_posts(channel_uuid: &str, config: &Config) -> Result<Arc<Vec<PostSummary>>, anyhow::Error> {
+    let mut query = HashMap::new();
+    query.insert("uuid".to_string(), channel_uuid.to_string());
+    let backend_api = BackendApi::new_v1(config);
+    let bytes = backend_api.get_bytes("channel/posts", query).await?;
+    let mut posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+    posts.sort_by_key(|post| std::cmp::Reverse(post.revision_date));
+
+    let posts = Arc::new(posts);
+    cache().insert(channel_uuid, posts.clone());
+    Ok(posts)
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct AdjacentPosts {
+    pub older: Option<PostSummary>,
+    pub newer: Option<PostSummary>,
+}
+
+/// Posts before and after the post in its channel, by revision date.
+pub(super) async fn adjacent_posts(post: &PostInfo, config: &Config) -> Result<AdjacentPosts, anyhow::Error> {
+    let channel_uuid = &post.channel.uuid;
+    let (mut posts, is_fresh) = match cache().get(channel_uuid) {
+        Some(posts) => (posts, false),
+        None => (fetch_channel_posts(channel_uuid, config).await?, true),
+    };
+    let mut index = posts.iter().position(|other| other.post_uuid == post.post_uuid);
+    let is_listed = index.map(|index| posts[index].revision_uuid == post.revision_uuid).unwrap_or(false);
+    if !is_listed && !is_fresh {
+        // the post is new or updated since the list was cached
+        posts = fetch_channel_posts(channel_uuid, config).await?;
+        index = posts.iter().position(|other| other.post_uuid == post.post_uuid);
+    }
+
+    let index = if let Some(index) = index {
+        index
+    } else {
+        return Ok(AdjacentPosts::default());
+    };
+    Ok(AdjacentPosts {
+        older: posts.get(index + 1).cloned(),
+        newer: index.checked_sub(1).and_then(|index| posts.get(index)).cloned(),
+    })
+}
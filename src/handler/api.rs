@@ -1,5 +1,8 @@
 
 use std::collections::HashMap;
+use std::env;
+use std::sync::OnceLock;
+use std::time::Duration;
 
 use serde::{Serialize, Deserialize};
 
@@ -7,11 +10,22 @@ use axum::{
     response::IntoResponse,
     Json,
     extract::Query,
+    extract::rejection::JsonRejection,
+    http::{HeaderMap, StatusCode},
 };
 
+use chrono::{Days, Locale, NaiveDate};
+use chrono_tz::Tz;
+
 use crate::markdown;
-use crate::config;
-use crate::unix_time::UnixTime;
+use crate::config::{self, Config};
+use crate::error_reporting::ApiError;
+use crate::unix_time::{UnixTime, DateTimePattern, parse_locale};
+use crate::calendar::Calendar;
+use crate::views;
+use crate::backend_api::BackendApi;
+use crate::cache::TtlCache;
+use crate::search::search_index;
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,18 +47,264 @@ pub async fn api_v1_config_get() -> impl IntoResponse {
     Json(config)
 }
 
+/// Upper limit of timestamps formatted in one request.
+const TIMESTAMP_FORMAT_BATCH_MAX: usize = 1000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimestampFormatOptions {
+    #[serde(default)]
+    timezone: Option<String>, // IANA name, defaults to server_timezone
+
+    #[serde(default)]
+    locale: Option<String>, // BCP 47 language tag
+
+    #[serde(default)]
+    pattern: Option<String>, // named pattern or strftime format
+
+    #[serde(default)]
+    relative: bool, // "3 days ago" instead of the pattern
+
+    #[serde(default)]
+    calendar_lang: Option<String>, // language of the content, selects the secondary calendar
+}
+
+impl TimestampFormatOptions {
+    fn from_query(params: &HashMap<String, String>) -> Result<Self, ApiError> {
+        let relative = match params.get("relative").map(|s| s.as_str()) {
+            None | Some("") | Some("0") | Some("false") => false,
+            Some("1") | Some("true") => true,
+            Some(_) => return Err(ApiError::bad_request("Invalid value for relative")),
+        };
+        Ok(Self {
+            timezone: params.get("timezone").cloned(),
+            locale: params.get("locale").cloned(),
+            pattern: params.get("pattern").cloned(),
+            relative,
+            calendar_lang: params.get("calendar_lang").cloned(),
+        })
+    }
+}
+
+struct TimestampFormatter {
+    timezone: Tz,
+    lang: String,
+    locale: Option<Locale>,
+    pattern: DateTimePattern,
+    relative: bool,
+    calendar: Option<Calendar>, // secondary
+    now: UnixTime,
+}
+
+impl TimestampFormatter {
+    fn try_new(options: &TimestampFormatOptions, config: &Config) -> Result<Self, ApiError> {
+        let timezone = match &options.timezone {
+            Some(timezone) => timezone.parse::<Tz>()
+                .map_err(|_| ApiError::bad_request(&format!("Unknown timezone: {}", timezone)))?,
+            None => config.server_timezone(),
+        };
+        let (lang, locale) = match &options.locale {
+            Some(lang) => {
+                let locale = parse_locale(lang)
+                    .ok_or_else(|| ApiError::bad_request(&format!("Unsupported locale: {}", lang)))?;
+                (lang.clone(), Some(locale))
+            },
+            None => (String::new(), None),
+        };
+        let pattern = match &options.pattern {
+            Some(pattern) => pattern.parse::<DateTimePattern>()
+                .map_err(|e| ApiError::bad_request(&e.to_string()))?,
+            None => DateTimePattern::Default,
+        };
+
+        Ok(Self {
+            timezone,
+            lang,
+            locale,
+            pattern,
+            relative: options.relative,
+            calendar: options.calendar_lang.as_deref().and_then(|lang| config.secondary_calendar(lang)),
+            now: UnixTime::now(),
+        })
+    }
+
+    fn format(&self, timestamp: UnixTime) -> serde_json::Value {
+        let formatted = if self.relative {
+            timestamp.format_relative(self.now, &self.lang)
+        } else {
+            timestamp.format_in_timezone(self.timezone, &self.pattern, self.locale)
+        };
+        serde_json::json!({
+            "timestamp": u64::from(timestamp),
+            "datetime": timestamp.to_utc_datetime_string(),
+            "formatted": formatted,
+            "formatted_alt": self.calendar
+                .and_then(|calendar| timestamp.format_calendar(self.timezone, calendar))
+                .unwrap_or_default(),
+        })
+    }
+}
+
 pub async fn api_v1_timestamp_format(
     Query(params): Query<HashMap<String, String>>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     let config = config::load_config().await;
-    let timestamp = params.get("timestamp").map(|s| s.as_str()).unwrap_or("");
-    let timestamp = timestamp.parse::<u64>().unwrap_or(0);
+    let timestamp = params.get("timestamp")
+        .ok_or_else(|| ApiError::bad_request("Missing timestamp"))?
+        .parse::<UnixTime>()
+        .map_err(|e| ApiError::bad_request(&e.to_string()))?;
 
-    let timestamp = UnixTime::new(timestamp);
+    let options = TimestampFormatOptions::from_query(&params)?;
+    let formatter = TimestampFormatter::try_new(&options, &config)?;
+    Ok(Json(formatter.format(timestamp)))
+}
 
-    let formatted = timestamp.default_format_in_timezone(config.server_timezone());
-    Json(serde_json::json!({
-        "datetime": timestamp.to_utc_datetime_string(),
-        "formatted": formatted,
-    }))
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestTimestampFormat {
+    timestamps: Vec<u64>,
+
+    #[serde(flatten)]
+    options: TimestampFormatOptions,
+}
+
+pub async fn api_v1_timestamp_format_batch(
+    request: Result<Json<RequestTimestampFormat>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Json(request) = request.map_err(|e| ApiError::bad_request(&e.body_text()))?;
+    if request.timestamps.len() > TIMESTAMP_FORMAT_BATCH_MAX {
+        return Err(ApiError::bad_request(&format!("Too many timestamps (max {})", TIMESTAMP_FORMAT_BATCH_MAX)));
+    }
+
+    let config = config::load_config().await;
+    let formatter = TimestampFormatter::try_new(&request.options, &config)?;
+    let results = request.timestamps.iter().map(|&secs| {
+        UnixTime::checked_new(secs)
+            .map(|timestamp| formatter.format(timestamp))
+            .ok_or_else(|| ApiError::bad_request(&format!("Timestamp out of range: {}", secs)))
+    }).collect::<Result<Vec<_>, ApiError>>()?;
+
+    Ok(Json(serde_json::json!({
+        "results": results,
+    })))
+}
+
+/// Days of view counts returned when no range is given.
+const VIEWS_DEFAULT_DAYS: u64 = 30;
+
+/// Longest range of view counts in one request, in days.
+const VIEWS_MAX_DAYS: i64 = 366;
+
+fn is_uuid(text: &str) -> bool {
+    text.len() == 36 && text.char_indices().all(|(i, c)| match i {
+        8 | 13 | 18 | 23 => c == '-',
+        _ => c.is_ascii_hexdigit(),
+    })
+}
+
+/// Compares without returning early, so that the time taken does not leak the token.
+fn token_matches(provided: &str, token: &str) -> bool {
+    provided.len() == token.len()
+        && provided.bytes().zip(token.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+const POST_EXISTS_CACHE_TTL: Duration = Duration::from_secs(3600);
+const POST_EXISTS_CACHE_CAPACITY: usize = 4096;
+
+static POST_EXISTS_CACHE: OnceLock<TtlCache<bool>> = OnceLock::new();
+
+fn post_exists_cache() -> &'static TtlCache<bool> {
+    POST_EXISTS_CACHE.get_or_init(|| TtlCache::new(POST_EXISTS_CACHE_TTL, POST_EXISTS_CACHE_CAPACITY))
+}
+
+/// Whether the post is in the search index or known to the backend.
+async fn post_exists(config: &Config, post_uuid: &str) -> Result<bool, anyhow::Error> {
+    if search_index().read().unwrap().has_post(post_uuid) {
+        return Ok(true);
+    }
+    if let Some(exists) = post_exists_cache().get(post_uuid) {
+        return Ok(exists);
+    }
+    let exists = BackendApi::new_v1(config).get_post(post_uuid).await?.is_some();
+    post_exists_cache().insert(post_uuid, exists);
+    Ok(exists)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestViewsRecord {
+    post_uuid: String,
+}
+
+/// Counts a view of a post rendered by JavaScript navigation.
+pub async fn api_v1_views_record(
+    headers: HeaderMap,
+    request: Result<Json<RequestViewsRecord>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Json(request) = request.map_err(|e| ApiError::bad_request(&e.body_text()))?;
+    if !is_uuid(&request.post_uuid) {
+        return Err(ApiError::bad_request("Invalid post_uuid"));
+    }
+
+    let post_uuid = request.post_uuid.to_lowercase();
+
+    let config = config::load_config().await;
+    let exists = post_exists(&config, &post_uuid).await.map_err(|e| {
+        log::warn!("Failed to check post {}: {}", post_uuid, e);
+        ApiError::new(StatusCode::BAD_GATEWAY, "Failed to check the post")
+    })?;
+    if !exists {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "No such post"));
+    }
+    views::record_view(&post_uuid, &headers, &config);
+    Ok(Json(serde_json::json!({})))
+}
+
+/// Views per post per day. Requires `Authorization: Bearer {VIEW_STATS_TOKEN}`.
+pub async fn api_v1_views_get(
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let token = env::var("VIEW_STATS_TOKEN").unwrap_or_default();
+    if token.is_empty() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "View statistics are disabled"));
+    }
+    let provided = headers.get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or("");
+    if !token_matches(provided, &token) {
+        return Err(ApiError::new(StatusCode::UNAUTHORIZED, "Invalid token"));
+    }
+
+    let parse_date = |name: &str| params.get(name)
+        .map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| ApiError::bad_request(&format!("Invalid {}: expected YYYY-MM-DD", name))))
+        .transpose();
+    let config = config::load_config().await;
+    let to = parse_date("to")?.unwrap_or_else(|| views::today(&config));
+    let from = parse_date("from")?.unwrap_or(to - Days::new(VIEWS_DEFAULT_DAYS - 1));
+    if from > to {
+        return Err(ApiError::bad_request("from is after to"));
+    }
+    if (to - from).num_days() >= VIEWS_MAX_DAYS {
+        return Err(ApiError::bad_request(&format!("Too long range (max {} days)", VIEWS_MAX_DAYS)));
+    }
+
+    let internal_error = |e: String| {
+        log::error!("Failed to read view counts: {}", e);
+        ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read view counts")
+    };
+    let mut daily_views = tokio::task::spawn_blocking(move || views::view_counter().daily_views(from, to))
+        .await
+        .map_err(|e| internal_error(e.to_string()))?
+        .map_err(|e| internal_error(e.to_string()))?;
+    if let Some(post_uuid) = params.get("post_uuid") {
+        daily_views.retain(|daily| &daily.post_uuid == post_uuid);
+    }
+    let total: u64 = daily_views.iter().map(|daily| daily.views).sum();
+
+    Ok(Json(serde_json::json!({
+        "from": from.format("%Y-%m-%d").to_string(),
+        "to": to.format("%Y-%m-%d").to_string(),
+        "total": total,
+        "views": daily_views,
+    })))
 }
@@ -0,0 +1,200 @@
+//! Date-based archives of the whole site and of channels.
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::response::{IntoResponse, Response};
+use axum::extract::Path;
+use axum::http::Request;
+use axum::body::Body;
+
+use askama::Template;
+
+use chrono::Datelike;
+
+use crate::error_reporting::result_into_response;
+use crate::config::{self, Config};
+use crate::template::{
+    HtmlTemplate,
+    BaseTemplate,
+    ContentArchiveTemplate,
+    ArchiveYear,
+    ArchiveMonth,
+};
+use crate::unix_time::UnixTime;
+use crate::backend_api::BackendApi;
+
+use super::{
+    PostSummary,
+    ChannelInfo,
+    render_post_list_items,
+    handler_404,
+};
+
+
+/// Year and month of the post's revision date in the server timezone.
+fn post_year_month(post: &PostSummary, config: &Config) -> (i32, u32) {
+    let datetime = UnixTime::new(post.revision_date).to_datetime(config.server_timezone());
+    (datetime.year(), datetime.month())
+}
+
+/// Month index with post counts, newest first.
+fn archive_index(posts: &[PostSummary], config: &Config, base_url: &str, period: (Option<i32>, Option<u32>)) -> Vec<ArchiveYear> {
+    let mut counts: BTreeMap<i32, BTreeMap<u32, usize>> = BTreeMap::new();
+    for post in posts {
+        let (year, month) = post_year_month(post, config);
+        *counts.entry(year).or_default().entry(month).or_default() += 1;
+    }
+
+    counts.into_iter().rev().map(|(year, months)| {
+        let months: Vec<ArchiveMonth> = months.into_iter().rev().map(|(month, count)| {
+            ArchiveMonth {
+                label: format!("{:04}-{:02}", year, month),
+                url: format!("{}{:04}/{:02}/", base_url, year, month),
+                count,
+                is_current: period == (Some(year), Some(month)),
+            }
+        }).collect();
+        ArchiveYear {
+            label: format!("{:04}", year),
+            url: format!("{}{:04}/", base_url, year),
+            count: months.iter().map(|month| month.count).sum(),
+            is_current: period.0 == Some(year) && period.1.is_none(),
+            months,
+        }
+    }).collect()
+}
+
+/// Parses `:year` and `:month` path parameters. Returns `None` if invalid.
+fn parse_period(params: &HashMap<String, String>) -> Option<(Option<i32>, Option<u32>)> {
+    let year = match params.get("year") {
+        Some(year) => Some(year.parse::<i32>().ok().filter(|year| (1970..=9999).contains(year))?),
+        None => None,
+    };
+    let month = match params.get("month") {
+        Some(month) => Some(month.parse::<u32>().ok().filter(|month| (1..=12).contains(month))?),
+        None => None,
+    };
+    Some((year, month))
+}
+
+fn period_label(period: (Option<i32>, Option<u32>)) -> String {
+    match period {
+        (Some(year), Some(month)) => format!("{:04}-{:02}", year, month),
+        (Some(year), None) => format!("{:04}", year),
+        _ => "".to_string(),
+    }
+}
+
+fn render_archive(
+    url: &str,
+    config: &Config,
+    mut posts: Vec<PostSummary>,
+    channel: Option<&ChannelInfo>,
+    period: (Option<i32>, Option<u32>),
+) -> Result<Response, anyhow::Error> {
+    let base_url = match channel {
+        Some(channel) => format!("/c/{}/archive/", channel.handle),
+        None => "/archive/".to_string(),
+    };
+
+    posts.sort_by_key(|post| std::cmp::Reverse(post.revision_date));
+    let archive_years = archive_index(&posts, config, &base_url, period);
+
+    let post_list_html = if period.0.is_some() {
+        let posts: Vec<PostSummary> = posts.into_iter().filter(|post| {
+            let (year, month) = post_year_month(post, config);
+            Some(year) == period.0 && period.1.map(|m| m == month).unwrap_or(true)
+        }).collect();
+        render_post_list_items(&posts, config)?
+    } else {
+        String::new()
+    };
+
+    let period_label = period_label(period);
+    let archive_title = if period_label.is_empty() {
+        "Archive".to_string()
+    } else {
+        format!("Archive: {}", period_label)
+    };
+    let page_title = match channel {
+        Some(channel) => format!("{} - {}", archive_title, channel.name),
+        None => archive_title.clone(),
+    };
+
+    let content_template = ContentArchiveTemplate {
+        archive_title,
+        archive_url: base_url,
+        channel_handle: channel.map(|channel| channel.handle.clone()).unwrap_or_default(),
+        channel_name: channel.map(|channel| channel.name.clone()).unwrap_or_default(),
+        archive_years,
+        post_list_html,
+    };
+
+    let template = BaseTemplate::try_new(
+        url,
+        Some(&page_title),
+        &content_template.render()?,
+        config,
+    )?;
+
+    Ok(HtmlTemplate(template).into_response())
+}
+
+pub async fn handler_archive(
+    params: Option<Path<HashMap<String, String>>>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let params = params.map(|Path(params)| params).unwrap_or_default();
+        let period = if let Some(period) = parse_period(&params) {
+            period
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+
+        let backend_api = BackendApi::new_v1(&config);
+        let bytes = backend_api.get_bytes("post/list", HashMap::new()).await?;
+        let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+
+        render_archive(&url, &config, posts, None, period)
+    }).await
+}
+
+pub async fn handler_channel_archive(
+    Path(params): Path<HashMap<String, String>>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let period = if let Some(period) = parse_period(&params) {
+            period
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+
+        let mut query = HashMap::new();
+        query.insert("handle".to_string(), params.get("channel_handle").cloned().unwrap_or_default());
+        let backend_api = BackendApi::new_v1(&config);
+        let bytes = if let Ok(bytes) = backend_api.get_bytes("channel/info", query).await {
+            bytes
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+        let channel: ChannelInfo = serde_json::from_slice(&bytes)?;
+
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), channel.uuid.to_string());
+        let bytes = backend_api.get_bytes("channel/posts", query).await?;
+        let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+        let posts: Vec<PostSummary> = posts.into_iter()
+            .map(|post| post.with_channel(channel.summary()))
+            .collect();
+
+        render_archive(&url, &config, posts, Some(&channel), period)
+    }).await
+}
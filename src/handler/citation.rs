@@ -0,0 +1,64 @@
+//! Citation exports of posts.
+
+use axum::response::IntoResponse;
+use axum::extract::Path;
+use axum::http::{Request, header};
+use axum::body::Body;
+
+use crate::error_reporting::result_into_response;
+use crate::config;
+use crate::backend_api::BackendApi;
+use crate::citation::{Citation, CitationFormat};
+
+use super::{
+    handler_404,
+};
+
+
+async fn citation_response(
+    channel_handle: String,
+    post_uuid: String,
+    request: Request<Body>,
+    format: CitationFormat,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+
+        let backend_api = BackendApi::new_v1(&config);
+        let post = match backend_api.get_post(&post_uuid).await? {
+            Some(post) if post.channel.handle == channel_handle => post,
+            _ => return Ok(handler_404(request).await.into_response()),
+        };
+
+        let citation = Citation::new(&post, &config)?;
+        let disposition = format!("inline; filename=\"{}.{}\"", citation.key, format.extension());
+        Ok((
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (header::CONTENT_DISPOSITION, disposition),
+            ],
+            citation.format(format)?,
+        ).into_response())
+    }).await
+}
+
+pub async fn handler_post_bibtex(
+    Path((channel_handle, post_uuid)): Path<(String, String)>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    citation_response(channel_handle, post_uuid, request, CitationFormat::BibTex).await
+}
+
+pub async fn handler_post_ris(
+    Path((channel_handle, post_uuid)): Path<(String, String)>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    citation_response(channel_handle, post_uuid, request, CitationFormat::Ris).await
+}
+
+pub async fn handler_post_csl_json(
+    Path((channel_handle, post_uuid)): Path<(String, String)>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    citation_response(channel_handle, post_uuid, request, CitationFormat::CslJson).await
+}
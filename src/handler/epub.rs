@@ -0,0 +1,281 @@
+//! EPUB exports of the posts of channels and authors.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use axum::response::{IntoResponse, Response};
+use axum::extract::Path;
+use axum::http::{Request, header};
+use axum::body::Body;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use url::Url;
+
+use crate::error_reporting::result_into_response;
+use crate::config::{self, Config};
+use crate::backend_api::BackendApi;
+use crate::cache::TtlCache;
+use crate::epub::{EpubBook, EpubChapter};
+use crate::markdown;
+use crate::unix_time::UnixTime;
+
+use super::series::try_first_revision_date;
+use super::{
+    PostSummary,
+    ChannelInfo,
+    AuthorInfo,
+    handler_404,
+};
+
+
+// subject and revisions -> EPUB container
+const CACHE_TTL: Duration = Duration::from_secs(3600);
+const CACHE_CAPACITY: usize = 16;
+
+const CONTENT_TYPE: &str = "application/epub+zip";
+
+/// Books have at most this many chapters, from the most recently edited posts.
+const MAX_CHAPTERS: usize = 500;
+
+/// Posts fetched from the backend at once while building a book.
+const FETCH_CONCURRENCY: usize = 8;
+
+
+static EPUB_CACHE: OnceLock<TtlCache<Arc<Vec<u8>>>> = OnceLock::new();
+
+fn cache() -> &'static TtlCache<Arc<Vec<u8>>> {
+    EPUB_CACHE.get_or_init(|| TtlCache::new(CACHE_TTL, CACHE_CAPACITY))
+}
+
+// cache key -> lock held while the book is built
+static BUILD_LOCKS: OnceLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> = OnceLock::new();
+
+fn build_lock(key: &str) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = BUILD_LOCKS.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap();
+    // locks only referenced here are not held by any build
+    locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+    locks.entry(key.to_string()).or_default().clone()
+}
+
+/// The cached book, or the one built now. Requests for the same book wait for a single build.
+async fn cached_or_build<F>(key: &str, build: F) -> Result<Arc<Vec<u8>>, anyhow::Error>
+where
+    F: Future<Output = Result<Vec<u8>, anyhow::Error>>,
+{
+    if let Some(bytes) = cache().get(key) {
+        return Ok(bytes);
+    }
+    let lock = build_lock(key);
+    let _guard = lock.lock().await;
+    if let Some(bytes) = cache().get(key) {
+        return Ok(bytes);
+    }
+    let bytes = Arc::new(build.await?);
+    cache().insert(key, bytes.clone());
+    Ok(bytes)
+}
+
+/// Changes whenever a post is added, edited or deleted.
+fn cache_key(subject: &str, posts: &[PostSummary]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for post in posts {
+        post.revision_uuid.hash(&mut hasher);
+    }
+    format!("{}:{:016x}", subject, hasher.finish())
+}
+
+/// The `MAX_CHAPTERS` most recently edited posts, with a notice if others are left out.
+fn limit_posts(posts: &[PostSummary]) -> (Vec<PostSummary>, String) {
+    let mut posts = posts.to_vec();
+    if posts.len() <= MAX_CHAPTERS {
+        return (posts, String::new());
+    }
+    let total = posts.len();
+    posts.sort_by_key(|post| std::cmp::Reverse(post.revision_date));
+    posts.truncate(MAX_CHAPTERS);
+    let notice = format!("This book contains the {} most recently edited of {} posts.", MAX_CHAPTERS, total);
+    (posts, notice)
+}
+
+/// Chapters in the order the posts were first published, skipping deleted ones.
+/// Posts are fetched `FETCH_CONCURRENCY` at a time; any failure fails the whole book.
+async fn fetch_chapters(
+    backend_api: &BackendApi,
+    posts: &[PostSummary],
+    with_channel: bool,
+    config: &Config,
+) -> Result<Vec<EpubChapter>, anyhow::Error> {
+    let top_url = Url::parse(&config.top_url)?;
+    let timezone = config.server_timezone();
+
+    let permits = Arc::new(Semaphore::new(FETCH_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for summary in posts {
+        let backend_api = backend_api.clone();
+        let post_uuid = summary.post_uuid.clone();
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await?;
+            let post = match backend_api.get_post(&post_uuid).await? {
+                Some(post) => post,
+                None => return Ok(None),
+            };
+            let first_date = try_first_revision_date(&backend_api, &post_uuid).await?.unwrap_or(post.revision_date);
+            Ok::<_, anyhow::Error>(Some((first_date, post)))
+        });
+    }
+    let mut posts = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        if let Some(post) = result?? {
+            posts.push(post);
+        }
+    }
+    posts.sort_by(|(a_date, a), (b_date, b)| a_date.cmp(b_date).then_with(|| a.title.cmp(&b.title)));
+
+    let mut chapters = Vec::new();
+    for (_, post) in posts {
+        let date = UnixTime::new(post.revision_date);
+        let byline = if with_channel {
+            format!("{} · {}", post.author.name, post.channel.name)
+        } else {
+            post.author.name.clone()
+        };
+        chapters.push(EpubChapter {
+            title: post.title.clone(),
+            byline,
+            lang: post.channel.lang.clone(),
+            date: date.default_format_in_timezone(timezone),
+            date_value: date.to_datetime(timezone).to_rfc3339(),
+            url: top_url.join(&post.url())?.to_string(),
+            content_html: markdown::to_html(&post.revision_text),
+        });
+    }
+    Ok(chapters)
+}
+
+/// Most common language of the chapters, or the site language.
+fn book_lang(chapters: &[EpubChapter], config: &Config) -> String {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for chapter in chapters.iter().filter(|chapter| !chapter.lang.is_empty()) {
+        *counts.entry(chapter.lang.as_str()).or_default() += 1;
+    }
+    counts.into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(lang, _)| lang.to_string())
+        .unwrap_or_else(|| config.site_lang.clone())
+}
+
+fn epub_response(bytes: Arc<Vec<u8>>, file_name: &str) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, CONTENT_TYPE.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.epub\"", file_name)),
+        ],
+        bytes.as_ref().clone(),
+    ).into_response()
+}
+
+pub async fn handler_channel_epub(
+    Path(channel_handle): Path<String>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+
+        let mut query = HashMap::new();
+        query.insert("handle".to_string(), channel_handle);
+        let backend_api = BackendApi::new_v1(&config);
+        let bytes = if let Ok(bytes) = backend_api.get_bytes("channel/info", query).await {
+            bytes
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+        let channel: ChannelInfo = serde_json::from_slice(&bytes)?;
+
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), channel.uuid.to_string());
+        let bytes = backend_api.get_bytes("channel/posts", query).await?;
+        let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+
+        let key = cache_key(&format!("channel:{}", channel.uuid), &posts);
+        let bytes = cached_or_build(&key, async {
+            let (included, notice) = limit_posts(&posts);
+            let chapters = fetch_chapters(&backend_api, &included, false, &config).await?;
+            // bylines are the author names here
+            let mut creators: Vec<String> = Vec::new();
+            for chapter in &chapters {
+                if !creators.contains(&chapter.byline) {
+                    creators.push(chapter.byline.clone());
+                }
+            }
+            let modified = posts.iter().map(|post| post.revision_date).max().unwrap_or(channel.created_date);
+            let book = EpubBook {
+                identifier: format!("urn:uuid:{}", channel.uuid),
+                title: channel.name.clone(),
+                creators,
+                publisher: config.site_name.clone(),
+                description_html: markdown::to_html(&channel.description_text),
+                description: markdown::to_plain_text(&channel.description_text),
+                lang: channel.lang.clone(),
+                url: Url::parse(&config.top_url)?.join(&format!("/c/{}/", channel.handle))?.to_string(),
+                modified: UnixTime::new(modified),
+                notice,
+                chapters,
+            };
+            book.to_bytes()
+        }).await?;
+        Ok(epub_response(bytes, &channel.handle))
+    }).await
+}
+
+pub async fn handler_author_epub(
+    Path(author_uuid): Path<String>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), author_uuid);
+        let backend_api = BackendApi::new_v1(&config);
+        let bytes = if let Ok(bytes) = backend_api.get_bytes("author/info", query).await {
+            bytes
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+        let author: AuthorInfo = serde_json::from_slice(&bytes)?;
+
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), author.uuid.to_string());
+        let bytes = backend_api.get_bytes("author/posts", query).await?;
+        let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+
+        let key = cache_key(&format!("author:{}", author.uuid), &posts);
+        let bytes = cached_or_build(&key, async {
+            let (included, notice) = limit_posts(&posts);
+            let chapters = fetch_chapters(&backend_api, &included, true, &config).await?;
+            let modified = posts.iter().map(|post| post.revision_date).max().unwrap_or(author.created_date);
+            let book = EpubBook {
+                identifier: format!("urn:uuid:{}", author.uuid),
+                title: author.name.clone(),
+                creators: vec![author.name.clone()],
+                publisher: config.site_name.clone(),
+                description_html: markdown::to_html(&author.description_text),
+                description: markdown::to_plain_text(&author.description_text),
+                lang: book_lang(&chapters, &config),
+                url: Url::parse(&config.top_url)?.join(&format!("/authors/{}/", author.uuid))?.to_string(),
+                modified: UnixTime::new(modified),
+                notice,
+                chapters,
+            };
+            book.to_bytes()
+        }).await?;
+        Ok(epub_response(bytes, &author.uuid))
+    }).await
+}
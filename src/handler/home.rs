@@ -0,0 +1,164 @@
+//! Sections of the top page, as configured in `home_sections`.
+
+use std::collections::HashMap;
+
+use askama::Template;
+
+use crate::config::{Config, HomeSection};
+use crate::template::{
+    ContentPostListTemplate,
+    ContentPopularPostsTemplate,
+    ContentMetaPageTemplate,
+};
+use crate::backend_api::BackendApi;
+use crate::unix_time::UnixTime;
+use crate::markdown;
+
+use super::{
+    PostSummary,
+    ChannelSummary,
+    ChannelInfo,
+    MetaPage,
+    language,
+    popular,
+    fetch_posts,
+    tag_posts,
+    render_post_list_items,
+    render_channel_list_items,
+};
+
+
+const DEFAULT_POPULAR_LIMIT: usize = 5;
+
+
+fn filter_posts(posts: Vec<PostSummary>, lang: Option<&str>, limit: Option<usize>) -> Vec<PostSummary> {
+    let mut posts = match lang {
+        Some(lang) => language::filter_posts_by_lang(posts, lang),
+        None => posts,
+    };
+    if let Some(limit) = limit {
+        posts.truncate(limit);
+    }
+    posts
+}
+
+fn render_post_list(title: &str, posts: &[PostSummary], config: &Config) -> Result<String, anyhow::Error> {
+    let content_template = ContentPostListTemplate {
+        post_list_title: title.to_string(),
+        post_list_html: render_post_list_items(posts, config)?,
+    };
+    Ok(content_template.render()?)
+}
+
+/// Renders the section. Sections other than the latest posts are left out when empty.
+async fn render_section(
+    section: &HomeSection,
+    latest_posts: &[PostSummary],
+    lang: Option<&str>,
+    config: &Config,
+) -> Result<String, anyhow::Error> {
+    let backend_api = BackendApi::new_v1(config);
+    match section {
+        HomeSection::Featured { title, posts } => {
+            let posts = filter_posts(fetch_posts(&backend_api, posts).await?, lang, None);
+            if posts.is_empty() {
+                return Ok(String::new());
+            }
+            render_post_list(title.as_deref().unwrap_or("Featured"), &posts, config)
+        },
+        HomeSection::Latest { title, limit } => {
+            let posts = filter_posts(latest_posts.to_vec(), lang, *limit);
+            let title = match title {
+                Some(title) => title.clone(),
+                None => format!("Latest Posts{}", language::title_suffix(lang)),
+            };
+            render_post_list(&title, &posts, config)
+        },
+        HomeSection::Popular { limit } => {
+            let posts = popular::popular_posts(config).await?.as_ref().clone();
+            let posts = filter_posts(posts, lang, Some(limit.unwrap_or(DEFAULT_POPULAR_LIMIT)));
+            if posts.is_empty() {
+                return Ok(String::new());
+            }
+            let content_template = ContentPopularPostsTemplate {
+                post_list_html: render_post_list_items(&posts, config)?,
+            };
+            Ok(content_template.render()?)
+        },
+        HomeSection::Channel { title, handle, limit } => {
+            let mut query = HashMap::new();
+            query.insert("handle".to_string(), handle.clone());
+            let bytes = backend_api.get_bytes("channel/info", query).await?;
+            let channel: ChannelInfo = serde_json::from_slice(&bytes)?;
+
+            let mut query = HashMap::new();
+            query.insert("uuid".to_string(), channel.uuid.clone());
+            let bytes = backend_api.get_bytes("channel/posts", query).await?;
+            let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+            let mut posts: Vec<PostSummary> = posts.into_iter()
+                .map(|post| post.with_channel(channel.summary()))
+                .collect();
+            posts.sort_by_key(|post| std::cmp::Reverse(post.revision_date));
+            let posts = filter_posts(posts, lang, *limit);
+            if posts.is_empty() {
+                return Ok(String::new());
+            }
+            render_post_list(title.as_deref().unwrap_or(&channel.name), &posts, config)
+        },
+        HomeSection::Tag { title, tag_name, limit } => {
+            let mut posts = tag_posts(&backend_api, tag_name).await?;
+            posts.sort_by_key(|post| std::cmp::Reverse(post.revision_date));
+            let posts = filter_posts(posts, lang, *limit);
+            if posts.is_empty() {
+                return Ok(String::new());
+            }
+            let title = match title {
+                Some(title) => title.clone(),
+                None => format!("Tag: {}", tag_name),
+            };
+            render_post_list(&title, &posts, config)
+        },
+        HomeSection::ChannelDirectory { title } => {
+            let bytes = backend_api.get_bytes("channel/list", HashMap::new()).await?;
+            let channels: Vec<ChannelSummary> = serde_json::from_slice(&bytes)?;
+            let channels = match lang {
+                Some(lang) => language::filter_channels_by_lang(channels, lang),
+                None => channels,
+            };
+            if channels.is_empty() {
+                return Ok(String::new());
+            }
+            let content_template = ContentPostListTemplate {
+                post_list_title: title.clone().unwrap_or_else(|| "Channels".to_string()),
+                post_list_html: render_channel_list_items(&channels)?,
+            };
+            Ok(content_template.render()?)
+        },
+        HomeSection::MetaPage { page_name } => {
+            let mut query = HashMap::new();
+            query.insert("page_name".to_string(), page_name.clone());
+            let bytes = backend_api.get_bytes("meta/info", query).await?;
+            let meta_page: MetaPage = serde_json::from_slice(&bytes)?;
+            let updated_date = UnixTime::new(meta_page.updated_date);
+            let content_template = ContentMetaPageTemplate {
+                content_heading: meta_page.title.clone(),
+                content_date: updated_date.default_format_in_timezone(config.server_timezone()),
+                content_date_value: updated_date.to_utc_datetime_string(),
+                content_html: markdown::to_html(&meta_page.text),
+            };
+            Ok(content_template.render()?)
+        },
+    }
+}
+
+/// Renders the configured sections in order. A section that fails is logged and left out.
+pub(super) async fn render_sections(latest_posts: &[PostSummary], lang: Option<&str>, config: &Config) -> Result<String, anyhow::Error> {
+    let mut html = String::new();
+    for section in &config.home_sections {
+        match render_section(section, latest_posts, lang, config).await {
+            Ok(section_html) => html.push_str(&section_html),
+            Err(e) => log::warn!("Failed to render home section {:?}: {}", section, e),
+        }
+    }
+    Ok(html)
+}
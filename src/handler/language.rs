@@ -0,0 +1,207 @@
+//! Filtering by the language of channels, and per-language landing pages.
+
+use std::collections::{BTreeSet, HashMap};
+
+use axum::response::IntoResponse;
+use axum::extract::Path;
+use axum::http::Request;
+use axum::body::Body;
+
+use askama::Template;
+use url::Url;
+
+use crate::error_reporting::result_into_response;
+use crate::config::{self, Config};
+use crate::template::{
+    HtmlTemplate,
+    BaseTemplate,
+    AlternateLink,
+    ContentLangNavTemplate,
+    ContentPostListTemplate,
+    LangNavItem,
+};
+use crate::backend_api::BackendApi;
+use crate::lang::{self, LangTag};
+
+use super::{
+    PostSummary,
+    ChannelSummary,
+    render_post_list_items,
+    render_channel_list_items,
+    handler_404,
+};
+
+
+/// Language range from the `?lang=` query, if valid.
+pub(super) fn lang_param(params: &HashMap<String, String>) -> Option<String> {
+    params.get("lang")
+        .and_then(|lang| LangTag::parse(lang))
+        .map(|lang| lang.to_string())
+}
+
+/// Posts in channels matching the language range. Posts without a channel are dropped.
+pub(super) fn filter_posts_by_lang(posts: Vec<PostSummary>, lang: &str) -> Vec<PostSummary> {
+    posts.into_iter()
+        .filter(|post| post.channel.as_ref().map(|channel| lang::lang_matches(lang, &channel.lang)).unwrap_or(false))
+        .collect()
+}
+
+pub(super) fn filter_channels_by_lang(channels: Vec<ChannelSummary>, lang: &str) -> Vec<ChannelSummary> {
+    channels.into_iter()
+        .filter(|channel| lang::lang_matches(lang, &channel.lang))
+        .collect()
+}
+
+/// Primary languages of the posts' channels, sorted.
+pub(super) fn post_languages(posts: &[PostSummary]) -> Vec<String> {
+    languages(posts.iter().filter_map(|post| post.channel.as_ref()))
+}
+
+/// Primary languages of the channels, sorted.
+pub(super) fn languages<'a>(channels: impl Iterator<Item = &'a ChannelSummary>) -> Vec<String> {
+    channels
+        .filter_map(|channel| LangTag::parse(&channel.lang))
+        .map(|lang| lang.primary_language().to_string())
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+fn language_label(lang: &str) -> String {
+    match LangTag::parse(lang).and_then(|tag| lang::autonym(tag.primary_language())) {
+        Some(autonym) => autonym.to_string(),
+        None => lang.to_string(),
+    }
+}
+
+/// Title suffix for a view filtered by the language, e.g. ` (日本語)`.
+pub(super) fn title_suffix(lang: Option<&str>) -> String {
+    match lang {
+        Some(lang) => format!(" ({})", language_label(lang)),
+        None => "".to_string(),
+    }
+}
+
+/// Language navigation and hreflang alternates between the views of a page in
+/// each language. `url_for(None)` is the view with every language.
+pub(super) struct LanguageViews {
+    pub nav_html: String, // empty if there is only one language
+    pub alternate_links: Vec<AlternateLink>,
+}
+
+impl LanguageViews {
+    pub fn new(
+        languages: &[String],
+        current: Option<&str>,
+        url_for: impl Fn(Option<&str>) -> String,
+        config: &Config,
+    ) -> Result<Self, anyhow::Error> {
+        if languages.len() < 2 {
+            return Ok(Self {
+                nav_html: "".to_string(),
+                alternate_links: vec![],
+            });
+        }
+
+        let top_url = Url::parse(&config.top_url)?;
+        let mut alternate_links = vec![AlternateLink {
+            hreflang: "x-default".to_string(),
+            url: top_url.join(&url_for(None))?.to_string(),
+        }];
+        for lang in languages {
+            alternate_links.push(AlternateLink {
+                hreflang: lang.clone(),
+                url: top_url.join(&url_for(Some(lang)))?.to_string(),
+            });
+        }
+
+        let mut items = vec![LangNavItem {
+            label: "All languages".to_string(),
+            lang: "".to_string(),
+            url: url_for(None),
+            is_current: current.is_none(),
+        }];
+        for lang in languages {
+            items.push(LangNavItem {
+                label: language_label(lang),
+                lang: lang.clone(),
+                url: url_for(Some(lang)),
+                is_current: current == Some(lang.as_str()),
+            });
+        }
+        let nav_html = ContentLangNavTemplate {
+            items,
+        }.render()?;
+
+        Ok(Self {
+            nav_html,
+            alternate_links,
+        })
+    }
+}
+
+/// `{url}?lang={lang}`, or the url itself for every language.
+pub(super) fn lang_query_url(url: &str, lang: Option<&str>) -> String {
+    match lang {
+        Some(lang) => format!("{}?lang={}", url, urlencoding::encode(lang)),
+        None => url.to_string(),
+    }
+}
+
+pub async fn handler_lang(
+    Path(lang_code): Path<String>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let lang = if let Some(lang) = LangTag::parse(&lang_code) {
+            lang.to_string()
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+
+        let backend_api = BackendApi::new_v1(&config);
+        let bytes = backend_api.get_bytes("channel/list", HashMap::new()).await?;
+        let channels: Vec<ChannelSummary> = serde_json::from_slice(&bytes)?;
+        let languages = languages(channels.iter());
+        let channels = filter_channels_by_lang(channels, &lang);
+        if channels.is_empty() {
+            return Ok(handler_404(request).await.into_response());
+        }
+
+        let bytes = backend_api.get_bytes("post/list", HashMap::new()).await?;
+        let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+        let posts = filter_posts_by_lang(posts, &lang);
+
+        let language_views = LanguageViews::new(&languages, Some(&lang), |lang| match lang {
+            Some(lang) => format!("/lang/{}/", lang),
+            None => "/".to_string(),
+        }, &config)?;
+
+        let channel_list_template = ContentPostListTemplate {
+            post_list_title: "Channels".to_string(),
+            post_list_html: render_channel_list_items(&channels)?,
+        };
+        let post_list_template = ContentPostListTemplate {
+            post_list_title: "Latest Posts".to_string(),
+            post_list_html: render_post_list_items(&posts, &config)?,
+        };
+        let content_html = format!(
+            "{}{}{}",
+            language_views.nav_html,
+            channel_list_template.render()?,
+            post_list_template.render()?,
+        );
+
+        let template = BaseTemplate::try_new(
+            &url,
+            Some(&language_label(&lang)),
+            &content_html,
+            &config,
+        )?.with_alternate_links(language_views.alternate_links);
+
+        Ok(HtmlTemplate(template).into_response())
+    }).await
+}
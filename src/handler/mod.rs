@@ -1,19 +1,55 @@
 
 mod api;
+mod archive;
+mod search;
+mod related;
+mod language;
+mod adjacent;
+mod series;
+mod stats;
+mod popular;
+mod home;
+mod revisions;
+mod source;
+mod citation;
+mod epub;
 
 
 pub use api::api_v1_markdown_parse;
 pub use api::api_v1_config_get;
 pub use api::api_v1_timestamp_format;
-
-
-use std::collections::HashMap;
-
-use axum::response::IntoResponse;
-use axum::extract::Path;
+pub use api::api_v1_timestamp_format_batch;
+pub use api::api_v1_views_record;
+pub use api::api_v1_views_get;
+
+pub use archive::handler_archive;
+pub use archive::handler_channel_archive;
+pub use search::handler_search;
+pub use search::handler_opensearch;
+pub use language::handler_lang;
+pub use series::handler_series;
+pub use stats::handler_channel_stats;
+pub use stats::handler_author_stats;
+pub use revisions::handler_post_revisions;
+pub use revisions::handler_post_revision;
+pub use revisions::handler_post_diff;
+pub use source::handler_post_markdown;
+pub use source::handler_post_plain_text;
+pub use citation::handler_post_bibtex;
+pub use citation::handler_post_ris;
+pub use citation::handler_post_csl_json;
+pub use epub::handler_channel_epub;
+pub use epub::handler_author_epub;
+
+
+use std::collections::{HashMap, HashSet};
+
+use axum::response::{IntoResponse, Response};
+use axum::extract::{Path, Query};
 use axum::http::{
     Request,
     StatusCode,
+    header,
 };
 use axum::body::Body;
 
@@ -22,7 +58,7 @@ use askama::Template;
 use serde::{Serialize, Deserialize};
 
 use crate::error_reporting::result_into_response;
-use crate::config;
+use crate::config::{self, Config};
 use crate::template::{
     HtmlTemplate,
     BaseTemplate,
@@ -36,12 +72,23 @@ use crate::template::{
     ContentPostTemplate,
     ContentAuthorListItemTemplate,
     ContentAuthorTemplate,
+    ContentChannelContributorTemplate,
+    ContentAuthorChannelTemplate,
     ContentTagListItemTemplate,
     ContentTagTemplate,
+    ContentTagIndexTemplate,
+    ContentTagIndexItemTemplate,
+    TagIndexOption,
+    TagFilter,
 };
 use crate::unix_time::UnixTime;
 use crate::backend_api::BackendApi;
 use crate::markdown;
+use crate::lang::{self, TextDirection};
+use crate::search::search_index;
+use crate::views;
+use crate::pages::{local_pages, LocalPage};
+use crate::citation::Citation;
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +115,22 @@ pub struct PostSummary {
     pub channel: Option<ChannelSummary>,
 }
 
+impl PostSummary {
+    pub fn with_author(self, author: AuthorSummary) -> Self {
+        Self {
+            author: Some(author),
+            ..self
+        }
+    }
+
+    pub fn with_channel(self, channel: ChannelSummary) -> Self {
+        Self {
+            channel: Some(channel),
+            ..self
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelInfo {
     pub uuid: String,
@@ -78,6 +141,17 @@ pub struct ChannelInfo {
     pub description_text: String,
 }
 
+impl ChannelInfo {
+    pub fn summary(&self) -> ChannelSummary {
+        ChannelSummary {
+            uuid: self.uuid.clone(),
+            handle: self.handle.clone(),
+            name: self.name.clone(),
+            lang: self.lang.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PostInfo {
     pub post_uuid: String,
@@ -90,6 +164,30 @@ pub struct PostInfo {
     pub author: AuthorSummary,
 }
 
+impl PostInfo {
+    pub fn url(&self) -> String {
+        format!("/c/{}/{}/", self.channel.handle, self.post_uuid)
+    }
+
+    pub fn summary(&self) -> PostSummary {
+        PostSummary {
+            post_uuid: self.post_uuid.clone(),
+            revision_uuid: self.revision_uuid.clone(),
+            revision_date: self.revision_date,
+            title: self.title.clone(),
+            author: Some(self.author.clone()),
+            channel: Some(self.channel.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostRevisionSummary {
+    pub revision_uuid: String,
+    pub revision_date: u64,
+    pub title: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthorInfo {
     pub uuid: String,
@@ -98,52 +196,77 @@ pub struct AuthorInfo {
     pub description_text: String,
 }
 
-pub async fn handler_root(request: Request<Body>) -> impl IntoResponse {
+impl AuthorInfo {
+    pub fn summary(&self) -> AuthorSummary {
+        AuthorSummary {
+            uuid: self.uuid.clone(),
+            name: self.name.clone(),
+        }
+    }
+}
+
+/// Renders posts as list items, or a message if there is none.
+/// Every post must have its author and channel filled.
+fn render_post_list_items(posts: &[PostSummary], config: &Config) -> Result<String, anyhow::Error> {
+    let mut html = String::new();
+    if posts.is_empty() {
+        let content_template = ContentSingleParagraphMessageTemplate {
+            message: "There is no post in this list.".to_string(),
+        };
+        html.push_str(&content_template.render()?);
+    }
+    for post in posts {
+        let updated_date = UnixTime::new(post.revision_date);
+        let author = post.author.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Post without author: {}", post.post_uuid))?;
+        let channel = post.channel.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Post without channel: {}", post.post_uuid))?;
+        let content_template = ContentPostListItemTemplate {
+            post_uuid: post.post_uuid.clone(),
+            title: post.title.clone(),
+            date: updated_date.default_format_in_timezone(config.server_timezone()),
+            date_value: updated_date.to_utc_datetime_string(),
+            date_alt: config.secondary_date_string(&channel.lang, updated_date),
+            author_uuid: author.uuid.clone(),
+            author_name: author.name.clone(),
+            channel_handle: channel.handle.clone(),
+            channel_name: channel.name.clone(),
+            channel_lang: channel.lang.clone(),
+            dir: TextDirection::detect(&channel.lang, &post.title).to_string(),
+        };
+        html.push_str(&content_template.render()?);
+    }
+    Ok(html)
+}
+
+pub async fn handler_root(
+    Query(params): Query<HashMap<String, String>>,
+    request: Request<Body>,
+) -> impl IntoResponse {
     result_into_response(async move {
         let url = request.uri().path().to_string();
         let config = config::load_config().await;
+        let lang = language::lang_param(&params);
 
         let query = HashMap::new();
         let backend_api = BackendApi::new_v1(&config);
         let bytes = backend_api.get_bytes("post/list", query).await?;
         
         let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
-        let mut html = String::new();
-        if posts.is_empty() {
-            let content_template = ContentSingleParagraphMessageTemplate {
-                message: "There is no post in this list.".to_string(),
-            };
-            html.push_str(&content_template.render()?);
-        }
-        for post in posts {
-            let updated_date = UnixTime::new(post.revision_date);
-            let author = post.author.unwrap();
-            let channel = post.channel.unwrap();
-            let content_template = ContentPostListItemTemplate {
-                post_uuid: post.post_uuid.clone(),
-                title: post.title.clone(),
-                date: updated_date.default_format_in_timezone(config.server_timezone()),
-                date_value: updated_date.to_utc_datetime_string(),
-                author_uuid: author.uuid.clone(),
-                author_name: author.name.clone(),
-                channel_handle: channel.handle.clone(),
-                channel_name: channel.name.clone(),
-                channel_lang: channel.lang.clone(),
-            };
-            html.push_str(&content_template.render()?);
-        }
-
-        let content_template = ContentPostListTemplate {
-            post_list_title: "Latest Posts".to_string(),
-            post_list_html: html,
-        };
+        let language_views = language::LanguageViews::new(
+            &language::post_languages(&posts),
+            lang.as_deref(),
+            |lang| language::lang_query_url("/", lang),
+            &config,
+        )?;
+        let sections_html = home::render_sections(&posts, lang.as_deref(), &config).await?;
 
         let template = BaseTemplate::try_new(
             &url,
             None,
-            &content_template.render()?,
+            &format!("{}{}", language_views.nav_html, sections_html),
             &config,
-        )?;
+        )?.with_alternate_links(language_views.alternate_links);
 
         Ok(HtmlTemplate(template))
     }).await
@@ -188,11 +311,47 @@ pub async fn handler_404(request: Request<Body>) -> impl IntoResponse {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct MetaPage {
-    page_name: String,
-    updated_date: u64,
-    title: String,
-    text: String,
+pub struct MetaPage {
+    pub page_name: String,
+    pub updated_date: u64,
+    pub title: String,
+    pub text: String,
+}
+
+/// Renders the meta page at `/meta/:page_name/` or its vanity route.
+async fn render_meta_page(page_name: &str, request: Request<Body>, config: &Config) -> Result<Response, anyhow::Error> {
+    let url = request.uri().path().to_string();
+
+    let mut query = HashMap::new();
+    query.insert("page_name".to_string(), page_name.to_string());
+
+    let backend_api = BackendApi::new_v1(config);
+    let bytes = if let Ok(bytes) = backend_api.get_bytes("meta/info", query).await {
+        bytes
+    } else {
+        return Ok(handler_404(request).await.into_response());
+    };
+
+    let meta_page: MetaPage = serde_json::from_slice(&bytes)?;
+
+    let updated_date = UnixTime::new(meta_page.updated_date);
+    let html = markdown::to_html(&meta_page.text);
+
+    let content_template = ContentMetaPageTemplate {
+        content_heading: meta_page.title.clone(),
+        content_date: updated_date.default_format_in_timezone(config.server_timezone()),
+        content_date_value: updated_date.to_utc_datetime_string(),
+        content_html: html,
+    };
+
+    let template = BaseTemplate::try_new(
+        &url,
+        Some(&meta_page.title),
+        &content_template.render()?,
+        config,
+    )?.with_canonical_url(&config.meta_page_url(page_name))?;
+
+    Ok(HtmlTemplate(template).into_response())
 }
 
 pub async fn handler_meta(
@@ -201,46 +360,64 @@ pub async fn handler_meta(
 ) -> impl IntoResponse {
     result_into_response(async move {
         let config = config::load_config().await;
-        let url = request.uri().path().to_string();
 
-        let mut query = HashMap::new();
-        query.insert("page_name".to_string(), page_name);
-
-        let backend_api = BackendApi::new_v1(&config);
-        let bytes = if let Ok(bytes) = backend_api.get_bytes("meta/info", query).await {
-            bytes
-        } else {
-            return Ok(handler_404(request).await.into_response());
-        };
-
-        let meta_page: MetaPage = serde_json::from_slice(&bytes)?;
-
-        let updated_date = UnixTime::new(meta_page.updated_date);
-        let html = markdown::to_html(&meta_page.text);
+        if let Some(route) = config.meta_page_route(&page_name) {
+            let location = match request.uri().query() {
+                Some(query) => format!("{}?{}", route, query),
+                None => route.to_string(),
+            };
+            return Ok((StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, location)]).into_response());
+        }
+        render_meta_page(&page_name, request, &config).await
+    }).await
+}
 
-        let content_template = ContentMetaPageTemplate {
-            content_heading: meta_page.title.clone(),
-            content_date: updated_date.default_format_in_timezone(config.server_timezone()),
-            content_date_value: updated_date.to_utc_datetime_string(),
-            content_html: html,
-        };
+/// Renders a page from the content directory. Does not use the backend.
+fn render_local_page(page: &LocalPage, config: &Config) -> Result<Response, anyhow::Error> {
+    let date = UnixTime::parse_in_timezone(&page.date, config.server_timezone());
+    let content_template = ContentMetaPageTemplate {
+        content_heading: page.title.clone(),
+        content_date: date.map(|date| date.default_format_in_timezone(config.server_timezone())).unwrap_or_default(),
+        content_date_value: date.map(|date| date.to_utc_datetime_string()).unwrap_or_default(),
+        content_html: markdown::to_html(&page.text),
+    };
+
+    let template = BaseTemplate::try_new(
+        &page.path,
+        Some(&page.title),
+        &content_template.render()?,
+        config,
+    )?;
+
+    Ok(HtmlTemplate(template).into_response())
+}
 
-        let template = BaseTemplate::try_new(
-            &url,
-            Some(&meta_page.title),
-            &content_template.render()?,
-            &config,
-        )?;
+/// Serves local pages and vanity routes of meta pages, or the 404 page.
+pub async fn handler_fallback(request: Request<Body>) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let path = request.uri().path().to_string();
 
-        Ok(HtmlTemplate(template).into_response())
+        let local_page = local_pages().read().unwrap().get(&path).cloned();
+        if let Some(page) = local_page {
+            return render_local_page(&page, &config);
+        }
+        if let Some(page_name) = config.meta_page_for_route(&path) {
+            return render_meta_page(page_name, request, &config).await;
+        }
+        let with_slash = format!("{}/", path);
+        if config.meta_page_for_route(&with_slash).is_some() || local_pages().read().unwrap().get(&with_slash).is_some() {
+            return Ok((StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, with_slash)]).into_response());
+        }
+        Ok(handler_404(request).await.into_response())
     }).await
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct MetaPageListItem {
-    page_name: String,
-    updated_date: u64,
-    title: String,
+pub struct MetaPageListItem {
+    pub page_name: String,
+    pub updated_date: u64,
+    pub title: String,
 }
 
 pub async fn handler_meta_list(
@@ -266,6 +443,7 @@ pub async fn handler_meta_list(
         for meta_page in meta_pages {
             let updated_date = UnixTime::new(meta_page.updated_date);
             let content_template = ContentMetaPageListItemTemplate {
+                page_url: config.meta_page_url(&meta_page.page_name),
                 page_name: meta_page.page_name.clone(),
                 title: meta_page.title.clone(),
                 date: updated_date.default_format_in_timezone(config.server_timezone()),
@@ -290,12 +468,34 @@ pub async fn handler_meta_list(
     }).await
 }
 
+fn render_channel_list_items(channels: &[ChannelSummary]) -> Result<String, anyhow::Error> {
+    let mut html = String::new();
+    if channels.is_empty() {
+        let content_template = ContentSingleParagraphMessageTemplate {
+            message: "There is no channel in this list.".to_string(),
+        };
+        html.push_str(&content_template.render()?);
+    }
+    for channel in channels {
+        let content_template = ContentChannelListItemTemplate {
+            channel_handle: channel.handle.clone(),
+            channel_name: channel.name.clone(),
+            channel_lang: channel.lang.clone(),
+            dir: TextDirection::detect(&channel.lang, &channel.name).to_string(),
+        };
+        html.push_str(&content_template.render()?);
+    }
+    Ok(html)
+}
+
 pub async fn handler_channel_list(
+    Query(params): Query<HashMap<String, String>>,
     request: Request<Body>,
 ) -> impl IntoResponse {
     result_into_response(async move {
         let config = config::load_config().await;
         let url = request.uri().path().to_string();
+        let lang = language::lang_param(&params);
 
         let query = HashMap::new();
 
@@ -303,33 +503,30 @@ pub async fn handler_channel_list(
         let bytes = backend_api.get_bytes("channel/list", query).await?;
         
         let channels: Vec<ChannelSummary> = serde_json::from_slice(&bytes)?;
-        let mut html = String::new();
-        if channels.is_empty() {
-            let content_template = ContentSingleParagraphMessageTemplate {
-                message: "There is no channel in this list.".to_string(),
-            };
-            html.push_str(&content_template.render()?);
-        }
-        for channel in channels {
-            let content_template = ContentChannelListItemTemplate {
-                channel_handle: channel.handle.clone(),
-                channel_name: channel.name.clone(),
-                channel_lang: channel.lang.clone(),
-            };
-            html.push_str(&content_template.render()?);
-        }
+        let language_views = language::LanguageViews::new(
+            &language::languages(channels.iter()),
+            lang.as_deref(),
+            |lang| language::lang_query_url("/c/", lang),
+            &config,
+        )?;
+        let channels = match &lang {
+            Some(lang) => language::filter_channels_by_lang(channels, lang),
+            None => channels,
+        };
+        let html = render_channel_list_items(&channels)?;
 
+        let title = format!("Channels{}", language::title_suffix(lang.as_deref()));
         let content_template = ContentPostListTemplate {
-            post_list_title: "Channels".to_string(),
+            post_list_title: title.clone(),
             post_list_html: html,
         };
 
         let template = BaseTemplate::try_new(
             &url,
-            Some("Channels"),
-            &content_template.render()?,
+            Some(&title),
+            &format!("{}{}", language_views.nav_html, content_template.render()?),
             &config,
-        )?;
+        )?.with_alternate_links(language_views.alternate_links);
 
         Ok(HtmlTemplate(template).into_response())
     }).await
@@ -354,41 +551,41 @@ pub async fn handler_channel(
         let channel: ChannelInfo = serde_json::from_slice(&bytes)?;
 
         let mut query = HashMap::new();
-        query.insert("uuid".to_string(), (&channel.uuid).to_string());
+        query.insert("uuid".to_string(), channel.uuid.to_string());
         let bytes = backend_api.get_bytes("channel/posts", query).await?;
         let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
         
-        let mut html = String::new();
-        if posts.is_empty() {
-            let content_template = ContentSingleParagraphMessageTemplate {
-                message: "There is no channel in this list.".to_string(),
-            };
-            html.push_str(&content_template.render()?);
-        }
-        for post in &posts {
-            let updated_date = UnixTime::new(post.revision_date);
-            let author = post.author.clone().unwrap();
-            let content_template = ContentPostListItemTemplate {
-                post_uuid: post.post_uuid.clone(),
-                title: post.title.clone(),
-                date: updated_date.default_format_in_timezone(config.server_timezone()),
-                date_value: updated_date.to_utc_datetime_string(),
-                channel_handle: channel.handle.clone(),
-                channel_name: channel.name.clone(),
-                channel_lang: channel.lang.clone(),
-                author_name: author.name.clone(),
+        let posts: Vec<PostSummary> = posts.into_iter()
+            .map(|post| post.with_channel(channel.summary()))
+            .collect();
+        let html = render_post_list_items(&posts, &config)?;
+
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), channel.uuid.to_string());
+        let bytes = backend_api.get_bytes("channel/authors", query).await?;
+        let authors: Vec<AuthorSummary> = serde_json::from_slice(&bytes)?;
+        let mut contributor_list_html = String::new();
+        for author in authors {
+            let post_count = posts.iter()
+                .filter(|post| post.author.as_ref().map(|post_author| post_author.uuid == author.uuid).unwrap_or(false))
+                .count();
+            let content_template = ContentChannelContributorTemplate {
                 author_uuid: author.uuid.clone(),
+                author_name: author.name.clone(),
+                post_count,
             };
-            html.push_str(&content_template.render()?);
+            contributor_list_html.push_str(&content_template.render()?);
         }
 
         let content_template = ContentChannelTemplate {
             channel_handle: channel.handle.clone(),
             channel_name: channel.name.clone(),
             channel_lang: channel.lang.clone(),
+            dir: TextDirection::detect(&channel.lang, &channel.name).to_string(),
             channel_description_html: markdown::to_html(&channel.description_text),
             channel_date: UnixTime::new(channel.created_date).default_format_in_timezone(config.server_timezone()),
             channel_date_value: UnixTime::new(channel.created_date).to_utc_datetime_string(),
+            contributor_list_html,
             post_list_html: html,
         };
 
@@ -430,19 +627,64 @@ pub async fn handler_post(
             content_template.render()
         }).collect::<Result<Vec<String>, askama::Error>>()?.join("\n");
 
+        let related_posts = match related::related_posts(&post, &config).await {
+            Ok(posts) => posts,
+            Err(e) => {
+                log::warn!("Failed to find related posts of {}: {}", post.post_uuid, e);
+                vec![]
+            },
+        };
+        let related_post_list_html = if related_posts.is_empty() {
+            String::new()
+        } else {
+            render_post_list_items(&related_posts, &config)?
+        };
+
+        let adjacent_posts = match adjacent::adjacent_posts(&post, &config).await {
+            Ok(adjacent_posts) => adjacent_posts,
+            Err(e) => {
+                log::warn!("Failed to find adjacent posts of {}: {}", post.post_uuid, e);
+                adjacent::AdjacentPosts::default()
+            },
+        };
+        let post_url = |post: &PostSummary| format!("/c/{}/{}/", channel_handle, post.post_uuid);
+        let older_post_url = adjacent_posts.older.as_ref().map(post_url).unwrap_or_default();
+        let newer_post_url = adjacent_posts.newer.as_ref().map(post_url).unwrap_or_default();
+
+        let series_html = match series::render_series_navigation(&post, &config).await {
+            Ok(html) => html,
+            Err(e) => {
+                log::warn!("Failed to find series of {}: {}", post.post_uuid, e);
+                String::new()
+            },
+        };
+
         let updated_date = UnixTime::new(post.revision_date);
+        let dir = TextDirection::detect(&post.channel.lang, &post.title);
+        let title_dir = TextDirection::from_text(&post.title).unwrap_or(dir);
         let content_template = ContentPostTemplate {
             post_uuid: post.post_uuid.clone(),
             title: post.title.clone(),
             date: updated_date.default_format_in_timezone(config.server_timezone()),
             date_value: updated_date.to_utc_datetime_string(),
+            date_alt: config.secondary_date_string(&post.channel.lang, updated_date),
             channel_handle: post.channel.handle.clone(),
             channel_name: post.channel.name.clone(),
             channel_lang: post.channel.lang.clone(),
+            dir: dir.to_string(),
+            title_dir: title_dir.to_string(),
             content_html: markdown::to_html(&post.revision_text),
             author_uuid: post.author.uuid.clone(),
             author_name: post.author.name.clone(),
             tag_list_html: tag_html_list,
+            related_post_list_html,
+            series_html,
+            revision_notice_html: String::new(),
+            citation: Citation::new(&post, &config)?.to_text(),
+            older_post_url: older_post_url.clone(),
+            older_post_title: adjacent_posts.older.map(|post| post.title).unwrap_or_default(),
+            newer_post_url: newer_post_url.clone(),
+            newer_post_title: adjacent_posts.newer.map(|post| post.title).unwrap_or_default(),
         };
 
         let template = BaseTemplate::try_new(
@@ -450,7 +692,9 @@ pub async fn handler_post(
             Some(post.title.as_str()),
             &content_template.render()?,
             &config,
-        )?;
+        )?.with_sequence_links(&older_post_url, &newer_post_url)?;
+
+        views::record_view(&post.post_uuid, request.headers(), &config);
 
         Ok(HtmlTemplate(template).into_response())
     }).await
@@ -519,32 +763,34 @@ pub async fn handler_author(
         let author: AuthorInfo = serde_json::from_slice(&bytes)?;
 
         let mut query = HashMap::new();
-        query.insert("uuid".to_string(), (&author.uuid).to_string());
+        query.insert("uuid".to_string(), author.uuid.to_string());
         let bytes = backend_api.get_bytes("author/posts", query).await?;
         let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
         
-        let mut html = String::new();
-        if posts.is_empty() {
-            let content_template = ContentSingleParagraphMessageTemplate {
-                message: "There is no post in this list.".to_string(),
-            };
-            html.push_str(&content_template.render()?);
+        let posts: Vec<PostSummary> = posts.into_iter()
+            .map(|post| post.with_author(author.summary()))
+            .collect();
+        let html = render_post_list_items(&posts, &config)?;
+
+        // channels by the number of the author's posts in each
+        let mut channels: Vec<(ChannelSummary, usize)> = Vec::new();
+        for channel in posts.iter().filter_map(|post| post.channel.as_ref()) {
+            match channels.iter_mut().find(|(other, _)| other.uuid == channel.uuid) {
+                Some((_, post_count)) => *post_count += 1,
+                None => channels.push((channel.clone(), 1)),
+            }
         }
-        for post in &posts {
-            let updated_date = UnixTime::new(post.revision_date);
-            let channel = post.channel.clone().unwrap();
-            let content_template = ContentPostListItemTemplate {
-                post_uuid: post.post_uuid.clone(),
-                title: post.title.clone(),
-                date: updated_date.default_format_in_timezone(config.server_timezone()),
-                date_value: updated_date.to_utc_datetime_string(),
-                channel_handle: channel.handle.clone(),
-                channel_name: channel.name.clone(),
-                channel_lang: channel.lang.clone(),
-                author_name: author.name.clone(),
-                author_uuid: author.uuid.clone(),
+        channels.sort_by_key(|(_, post_count)| std::cmp::Reverse(*post_count));
+        let mut channel_list_html = String::new();
+        for (channel, post_count) in channels {
+            let content_template = ContentAuthorChannelTemplate {
+                dir: TextDirection::detect(&channel.lang, &channel.name).to_string(),
+                channel_handle: channel.handle,
+                channel_name: channel.name,
+                channel_lang: channel.lang,
+                post_count,
             };
-            html.push_str(&content_template.render()?);
+            channel_list_html.push_str(&content_template.render()?);
         }
 
         let content_template = ContentAuthorTemplate {
@@ -553,6 +799,7 @@ pub async fn handler_author(
             author_description_html: markdown::to_html(&author.description_text),
             author_date: UnixTime::new(author.created_date).default_format_in_timezone(config.server_timezone()),
             author_date_value: UnixTime::new(author.created_date).to_utc_datetime_string(),
+            channel_list_html,
             post_list_html: html,
         };
 
@@ -573,34 +820,188 @@ struct TagListItem {
     page_count: u64,
 }
 
+/// Number of weight levels in the tag cloud.
+const TAG_CLOUD_WEIGHTS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagListSort {
+    Count,
+    Name,
+    Recent,
+}
+
+impl TagListSort {
+    const ALL: [TagListSort; 3] = [TagListSort::Count, TagListSort::Name, TagListSort::Recent];
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "count" => Some(TagListSort::Count),
+            "name" => Some(TagListSort::Name),
+            "recent" => Some(TagListSort::Recent),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            TagListSort::Count => "count",
+            TagListSort::Name => "name",
+            TagListSort::Recent => "recent",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            TagListSort::Count => "Most used",
+            TagListSort::Name => "Name",
+            TagListSort::Recent => "Recently active",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagListView {
+    Index,
+    Cloud,
+}
+
+impl TagListView {
+    const ALL: [TagListView; 2] = [TagListView::Index, TagListView::Cloud];
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "index" => Some(TagListView::Index),
+            "cloud" => Some(TagListView::Cloud),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            TagListView::Index => "index",
+            TagListView::Cloud => "cloud",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            TagListView::Index => "Index",
+            TagListView::Cloud => "Cloud",
+        }
+    }
+}
+
+fn tag_list_url(sort: TagListSort, view: TagListView, min_page_count: u64) -> String {
+    format!("/tags/?sort={}&view={}&min={}", sort.as_str(), view.as_str(), min_page_count)
+}
+
+/// Weight from 1 to `TAG_CLOUD_WEIGHTS`, on a logarithmic scale between the counts.
+fn tag_cloud_weight(page_count: u64, min: u64, max: u64) -> u64 {
+    if max <= min {
+        return 1;
+    }
+    let position = ((page_count.max(1) as f64).ln() - (min.max(1) as f64).ln())
+        / ((max as f64).ln() - (min.max(1) as f64).ln());
+    1 + (position * (TAG_CLOUD_WEIGHTS - 1) as f64).round() as u64
+}
+
 pub async fn handler_tag_list(
+    Query(params): Query<HashMap<String, String>>,
     request: Request<Body>,
 ) -> impl IntoResponse {
     result_into_response(async move {
         let config = config::load_config().await;
         let url = request.uri().path().to_string();
 
+        let sort = params.get("sort").and_then(|sort| TagListSort::parse(sort)).unwrap_or(TagListSort::Count);
+        let view = params.get("view").and_then(|view| TagListView::parse(view)).unwrap_or(TagListView::Index);
+        let min_page_count = params.get("min")
+            .and_then(|min| min.parse::<u64>().ok())
+            .unwrap_or(config.tag_list_min_page_count);
+
         let query = HashMap::new();
 
         let backend_api = BackendApi::new_v1(&config);
         let bytes = backend_api.get_bytes("tag/list", query).await?;
         
         let tags: Vec<TagListItem> = serde_json::from_slice(&bytes)?;
+        let total_count = tags.len();
+        let mut tags: Vec<TagListItem> = tags.into_iter()
+            .filter(|tag| tag.page_count >= min_page_count)
+            .collect();
+        let hidden_count = total_count - tags.len();
+
+        let mut sort_notice = String::new();
+        match sort {
+            TagListSort::Count => {
+                let collator = lang::collator(&config.site_lang);
+                tags.sort_by(|a, b| {
+                    b.page_count.cmp(&a.page_count).then_with(|| collator.compare(&a.tag_name, &b.tag_name))
+                });
+            },
+            TagListSort::Name => {
+                let collator = lang::collator(&config.site_lang);
+                tags.sort_by(|a, b| collator.compare(&a.tag_name, &b.tag_name));
+            },
+            TagListSort::Recent => {
+                // tags not in the search index yet come last
+                let (activity, index_is_empty) = {
+                    let index = search_index().read().unwrap();
+                    (index.tag_activity(), index.is_empty())
+                };
+                tags.sort_by(|a, b| {
+                    activity.get(&b.tag_name).cmp(&activity.get(&a.tag_name))
+                        .then_with(|| b.page_count.cmp(&a.page_count))
+                });
+                let unknown_count = tags.iter().filter(|tag| !activity.contains_key(&tag.tag_name)).count();
+                if index_is_empty && !tags.is_empty() {
+                    sort_notice = "Post dates are still being indexed, so tags are listed by number of posts for now.".to_string();
+                } else if unknown_count > 0 {
+                    sort_notice = format!(
+                        "{} {} without indexed posts yet {} listed last, by number of posts.",
+                        unknown_count,
+                        if unknown_count == 1 { "tag" } else { "tags" },
+                        if unknown_count == 1 { "is" } else { "are" },
+                    );
+                }
+            },
+        }
+
+        let min_count = tags.iter().map(|tag| tag.page_count).min().unwrap_or(0);
+        let max_count = tags.iter().map(|tag| tag.page_count).max().unwrap_or(0);
+
         let mut html = String::new();
-        if tags.is_empty() {
-            let content_template = ContentSingleParagraphMessageTemplate {
-                message: "There is no tag in this list.".to_string(),
+        for tag in &tags {
+            let content_template = ContentTagIndexItemTemplate {
+                tag_name: tag.tag_name.clone(),
+                tag_name_urlencoded: urlencoding::encode(&tag.tag_name).to_string(),
+                page_count: tag.page_count,
+                weight: tag_cloud_weight(tag.page_count, min_count, max_count),
             };
             html.push_str(&content_template.render()?);
         }
-        for tag in tags {
-            let content_template = ContentTagListItemTemplate::new(&tag.tag_name);
-            html.push_str(&content_template.render()?);
-        }
 
-        let content_template = ContentPostListTemplate {
-            post_list_title: "Tags".to_string(),
-            post_list_html: html,
+        let sort_options = TagListSort::ALL.iter().map(|option| TagIndexOption {
+            label: option.label().to_string(),
+            url: tag_list_url(*option, view, min_page_count),
+            is_current: *option == sort,
+        }).collect();
+        let view_options = TagListView::ALL.iter().map(|option| TagIndexOption {
+            label: option.label().to_string(),
+            url: tag_list_url(sort, *option, min_page_count),
+            is_current: *option == view,
+        }).collect();
+
+        let content_template = ContentTagIndexTemplate {
+            is_cloud: view == TagListView::Cloud,
+            sort_options,
+            view_options,
+            tag_list_html: html,
+            is_empty: tags.is_empty(),
+            hidden_count,
+            min_page_count,
+            show_all_url: tag_list_url(sort, view, 0),
+            sort_notice,
         };
 
         let template = BaseTemplate::try_new(
@@ -614,53 +1015,204 @@ pub async fn handler_tag_list(
     }).await
 }
 
+/// At most this many tags can be combined in a tag page URL.
+const MAX_TAG_FILTERS: usize = 8;
+
+/// Tags of a tag page: `/tags/a+b/` for posts with all of the tags,
+/// `/tags/a/-b/-c/` for posts with `a` but neither `b` nor `c`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TagQuery {
+    included: Vec<String>,
+    excluded: Vec<String>,
+}
+
+impl TagQuery {
+    /// Parses the raw (still percent-encoded) path, so that `%2B` in a tag name
+    /// is not taken as a separator. Returns `None` if invalid.
+    fn parse(path: &str) -> Option<Self> {
+        let path = path.strip_prefix("/tags/")?;
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+
+        let decode = |name: &str| -> Option<String> {
+            let name = urlencoding::decode(name).ok()?.to_string();
+            if name.is_empty() { None } else { Some(name) }
+        };
+
+        let mut query = TagQuery {
+            included: vec![],
+            excluded: vec![],
+        };
+        for name in segments.next()?.split('+') {
+            let name = decode(name)?;
+            if !query.included.contains(&name) {
+                query.included.push(name);
+            }
+        }
+        for segment in segments {
+            let name = decode(segment.strip_prefix('-')?)?;
+            if !query.excluded.contains(&name) {
+                query.excluded.push(name);
+            }
+        }
+
+        if query.included.len() + query.excluded.len() > MAX_TAG_FILTERS {
+            return None;
+        }
+        Some(query)
+    }
+
+    fn included_path(&self) -> String {
+        self.included.iter()
+            .map(|name| urlencoding::encode(name).to_string())
+            .collect::<Vec<String>>()
+            .join("+")
+    }
+
+    /// `/tags/` if no tag is included.
+    fn url(&self) -> String {
+        if self.included.is_empty() {
+            return "/tags/".to_string();
+        }
+        let mut url = format!("/tags/{}/", self.included_path());
+        for name in &self.excluded {
+            url.push_str(&format!("-{}/", urlencoding::encode(name)));
+        }
+        url
+    }
+
+    fn title(&self) -> String {
+        let mut title = self.included.join(" + ");
+        for name in &self.excluded {
+            title.push_str(&format!(" \u{2212} {}", name));
+        }
+        title
+    }
+
+    /// Active filters, each with the URL without it.
+    fn filters(&self) -> Vec<TagFilter> {
+        let included = self.included.iter().map(|name| {
+            let query = TagQuery {
+                included: self.included.iter().filter(|other| *other != name).cloned().collect(),
+                excluded: self.excluded.clone(),
+            };
+            TagFilter {
+                tag_name: name.clone(),
+                is_excluded: false,
+                remove_url: query.url(),
+            }
+        });
+        let excluded = self.excluded.iter().map(|name| {
+            let query = TagQuery {
+                included: self.included.clone(),
+                excluded: self.excluded.iter().filter(|other| *other != name).cloned().collect(),
+            };
+            TagFilter {
+                tag_name: name.clone(),
+                is_excluded: true,
+                remove_url: query.url(),
+            }
+        });
+        included.chain(excluded).collect()
+    }
+}
+
+/// Posts in the given order, skipping deleted ones.
+async fn fetch_posts(backend_api: &BackendApi, post_uuids: &[String]) -> Result<Vec<PostSummary>, anyhow::Error> {
+    let mut posts = Vec::new();
+    for post_uuid in post_uuids {
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), post_uuid.to_string());
+        let bytes = backend_api.get_bytes("post/info", query).await?;
+        if let Ok(post) = serde_json::from_slice::<PostInfo>(&bytes) {
+            posts.push(post.summary());
+        }
+    }
+    Ok(posts)
+}
+
+async fn tag_posts(backend_api: &BackendApi, tag_name: &str) -> Result<Vec<PostSummary>, anyhow::Error> {
+    let mut query = HashMap::new();
+    query.insert("tag_name".to_string(), tag_name.to_string());
+    let bytes = backend_api.get_bytes("tag/posts", query).await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
 pub async fn handler_tag(
-    Path(tag_name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
     request: Request<Body>,
 ) -> impl IntoResponse {
     result_into_response(async move {
         let config = config::load_config().await;
         let url = request.uri().path().to_string();
+        let lang = language::lang_param(&params);
+
+        let tag_query = if let Some(tag_query) = TagQuery::parse(&url) {
+            tag_query
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
 
+        // posts with all the included tags, in the order of the first tag
         let backend_api = BackendApi::new_v1(&config);
-        let mut query = HashMap::new();
-        query.insert("tag_name".to_string(), (&tag_name).to_string());
-        let bytes = backend_api.get_bytes("tag/posts", query).await?;
-        let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
-        
-        let mut html = String::new();
-        if posts.is_empty() {
-            let content_template = ContentSingleParagraphMessageTemplate {
-                message: "There is no post in this list.".to_string(),
-            };
-            html.push_str(&content_template.render()?);
+        let mut posts: Vec<PostSummary> = vec![];
+        for (i, tag_name) in tag_query.included.iter().enumerate() {
+            let tag_posts = tag_posts(&backend_api, tag_name).await?;
+            if i == 0 {
+                posts = tag_posts;
+            } else {
+                let uuids: HashSet<String> = tag_posts.into_iter().map(|post| post.post_uuid).collect();
+                posts.retain(|post| uuids.contains(&post.post_uuid));
+            }
+            if posts.is_empty() {
+                break;
+            }
         }
-        for post in &posts {
-            let updated_date = UnixTime::new(post.revision_date);
-            let channel = post.channel.clone().unwrap();
-            let author = post.author.clone().unwrap();
-            let content_template = ContentPostListItemTemplate {
-                post_uuid: post.post_uuid.clone(),
-                title: post.title.clone(),
-                date: updated_date.default_format_in_timezone(config.server_timezone()),
-                date_value: updated_date.to_utc_datetime_string(),
-                channel_handle: channel.handle.clone(),
-                channel_name: channel.name.clone(),
-                channel_lang: channel.lang.clone(),
-                author_name: author.name.clone(),
-                author_uuid: author.uuid.clone(),
-            };
-            html.push_str(&content_template.render()?);
+        for tag_name in &tag_query.excluded {
+            if posts.is_empty() {
+                break;
+            }
+            let uuids: HashSet<String> = tag_posts(&backend_api, tag_name).await?
+                .into_iter()
+                .map(|post| post.post_uuid)
+                .collect();
+            posts.retain(|post| !uuids.contains(&post.post_uuid));
         }
 
-        let content_template = ContentTagTemplate::new(&tag_name, &html);
+        let language_views = language::LanguageViews::new(
+            &language::post_languages(&posts),
+            lang.as_deref(),
+            |lang| language::lang_query_url(&tag_query.url(), lang),
+            &config,
+        )?;
+        let posts = match &lang {
+            Some(lang) => language::filter_posts_by_lang(posts, lang),
+            None => posts,
+        };
+        
+        let html = render_post_list_items(&posts, &config)?;
+
+        let title = format!("{}{}", tag_query.title(), language::title_suffix(lang.as_deref()));
+        let content_template = ContentTagTemplate {
+            tag_name: title.clone(),
+            tag_name_urlencoded: tag_query.included_path(),
+            filters: if tag_query.included.len() + tag_query.excluded.len() > 1 {
+                tag_query.filters().into_iter().map(|filter| TagFilter {
+                    remove_url: language::lang_query_url(&filter.remove_url, lang.as_deref()),
+                    ..filter
+                }).collect()
+            } else {
+                vec![]
+            },
+            post_count: posts.len(),
+            post_list_html: html,
+        };
 
         let template = BaseTemplate::try_new(
             &url,
-            Some(format!("Tag: {}", tag_name).as_str()),
-            &content_template.render()?,
+            Some(format!("Tag: {}", title).as_str()),
+            &format!("{}{}", language_views.nav_html, content_template.render()?),
             &config,
-        )?;
+        )?.with_alternate_links(language_views.alternate_links);
 
         Ok(HtmlTemplate(template).into_response())
     }).await
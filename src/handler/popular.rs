@@ -0,0 +1,47 @@
+//! Posts with the most views in the last week.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use chrono::Days;
+
+use crate::config::Config;
+use crate::backend_api::BackendApi;
+use crate::cache::TtlCache;
+use crate::views;
+
+use super::{PostSummary, fetch_posts};
+
+
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+const PERIOD_DAYS: u64 = 7;
+
+/// Candidates resolved, so that enough remain after filtering by language.
+const MAX_CANDIDATES: usize = 30;
+
+
+static POPULAR_CACHE: OnceLock<TtlCache<Arc<Vec<PostSummary>>>> = OnceLock::new();
+
+fn cache() -> &'static TtlCache<Arc<Vec<PostSummary>>> {
+    POPULAR_CACHE.get_or_init(|| TtlCache::new(CACHE_TTL, 1))
+}
+
+async fn fetch_popular_posts(config: &Config) -> Result<Vec<PostSummary>, anyhow::Error> {
+    let today = views::today(config);
+    let from = today - Days::new(PERIOD_DAYS - 1);
+    let popular = tokio::task::spawn_blocking(move || views::view_counter().popular(from, today, MAX_CANDIDATES)).await??;
+
+    let post_uuids: Vec<String> = popular.into_iter().map(|(post_uuid, _)| post_uuid).collect();
+    fetch_posts(&BackendApi::new_v1(config), &post_uuids).await
+}
+
+/// Posts by views in the last seven days, most viewed first.
+pub(super) async fn popular_posts(config: &Config) -> Result<Arc<Vec<PostSummary>>, anyhow::Error> {
+    if let Some(posts) = cache().get("") {
+        return Ok(posts);
+    }
+    let posts = Arc::new(fetch_popular_posts(config).await?);
+    cache().insert("", posts.clone());
+    Ok(posts)
+}
@@ -0,0 +1,97 @@
+//! Related posts, found through the tags of a post.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::backend_api::BackendApi;
+use crate::cache::TtlCache;
+use crate::unix_time::UnixTime;
+
+use super::{PostInfo, PostSummary};
+
+
+/// At most this many tags of a post are looked up.
+const MAX_TAG_QUERIES: usize = 5;
+
+/// Score of a candidate per tag shared with the post.
+const TAG_SCORE: f64 = 1.0;
+
+/// Score of a candidate in the same channel as the post.
+const CHANNEL_SCORE: f64 = 0.5;
+
+/// Recency adds up to 1.0, halving every this many days.
+const RECENCY_HALF_LIFE_DAYS: f64 = 90.0;
+
+/// Cached results are recomputed after this time even for the same revision,
+/// so that newer posts show up.
+const CACHE_TTL: Duration = Duration::from_secs(3600);
+
+const CACHE_CAPACITY: usize = 1024;
+
+
+// revision uuid -> related posts
+static RELATED_POSTS_CACHE: OnceLock<TtlCache<Vec<PostSummary>>> = OnceLock::new();
+
+fn cache() -> &'static TtlCache<Vec<PostSummary>> {
+    RELATED_POSTS_CACHE.get_or_init(|| TtlCache::new(CACHE_TTL, CACHE_CAPACITY))
+}
+
+fn recency_score(post: &PostSummary, now: u64) -> f64 {
+    let age_days = now.saturating_sub(post.revision_date) as f64 / 86400.0;
+    0.5f64.powf(age_days / RECENCY_HALF_LIFE_DAYS)
+}
+
+/// Posts sharing tags with the post, best first. The current post is excluded.
+pub(super) async fn related_posts(post: &PostInfo, config: &Config) -> Result<Vec<PostSummary>, anyhow::Error> {
+    if config.related_posts_count == 0 || post.tags.is_empty() {
+        return Ok(vec![]);
+    }
+    if let Some(posts) = cache().get(&post.revision_uuid) {
+        return Ok(posts);
+    }
+
+    let backend_api = BackendApi::new_v1(config);
+    // post uuid -> (number of shared tags, post)
+    let mut candidates: HashMap<String, (usize, PostSummary)> = HashMap::new();
+    for tag_name in post.tags.iter().take(MAX_TAG_QUERIES) {
+        let mut query = HashMap::new();
+        query.insert("tag_name".to_string(), tag_name.to_string());
+        let bytes = backend_api.get_bytes("tag/posts", query).await?;
+        let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+        for candidate in posts {
+            if candidate.post_uuid == post.post_uuid {
+                continue;
+            }
+            candidates.entry(candidate.post_uuid.clone())
+                .or_insert((0, candidate))
+                .0 += 1;
+        }
+    }
+
+    let now = u64::from(UnixTime::now());
+    let mut scored: Vec<(f64, PostSummary)> = candidates.into_values()
+        .filter(|(_, candidate)| candidate.author.is_some() && candidate.channel.is_some())
+        .map(|(shared_tags, candidate)| {
+            let same_channel = candidate.channel.as_ref()
+                .map(|channel| channel.uuid == post.channel.uuid)
+                .unwrap_or(false);
+            let score = shared_tags as f64 * TAG_SCORE
+                + if same_channel { CHANNEL_SCORE } else { 0.0 }
+                + recency_score(&candidate, now);
+            (score, candidate)
+        })
+        .collect();
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+        b_score.total_cmp(a_score).then_with(|| b.revision_date.cmp(&a.revision_date))
+    });
+
+    let posts: Vec<PostSummary> = scored.into_iter()
+        .take(config.related_posts_count)
+        .map(|(_, candidate)| candidate)
+        .collect();
+    cache().insert(&post.revision_uuid, posts.clone());
+
+    Ok(posts)
+}
@@ -0,0 +1,249 @@
+//! Revision history of posts, past revisions, and changes between revisions.
+
+use std::collections::HashMap;
+
+use axum::response::IntoResponse;
+use axum::extract::{Path, Query};
+use axum::http::{Request, StatusCode, header};
+use axum::body::Body;
+
+use askama::Template;
+
+use crate::error_reporting::result_into_response;
+use crate::config;
+use crate::template::{
+    HtmlTemplate,
+    BaseTemplate,
+    ContentPostTemplate,
+    ContentPostRevisionsTemplate,
+    ContentPostRevisionNoticeTemplate,
+    ContentPostDiffTemplate,
+    ContentTagListItemTemplate,
+    PostRevisionItem,
+};
+use crate::unix_time::UnixTime;
+use crate::backend_api::BackendApi;
+use crate::markdown;
+use crate::lang::TextDirection;
+use crate::diff;
+
+use super::{
+    PostInfo,
+    handler_404,
+};
+
+
+fn revision_url(post: &PostInfo, revision_uuid: &str) -> String {
+    format!("{}r/{}/", post.url(), revision_uuid)
+}
+
+fn diff_url(post: &PostInfo, from_revision_uuid: &str, to_revision_uuid: &str) -> String {
+    format!("{}diff/?from={}&to={}",
+        post.url(),
+        urlencoding::encode(from_revision_uuid),
+        urlencoding::encode(to_revision_uuid),
+    )
+}
+
+pub async fn handler_post_revisions(
+    Path((channel_handle, post_uuid)): Path<(String, String)>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let backend_api = BackendApi::new_v1(&config);
+        let post = match backend_api.get_post(&post_uuid).await? {
+            Some(post) if post.channel.handle == channel_handle => post,
+            _ => return Ok(handler_404(request).await.into_response()),
+        };
+        let mut revisions = backend_api.get_post_revisions(&post.post_uuid).await?.unwrap_or_default();
+        revisions.sort_by_key(|revision| std::cmp::Reverse(revision.revision_date));
+
+        let timezone = config.server_timezone();
+        let revisions = revisions.iter().enumerate().map(|(i, revision)| {
+            let date = UnixTime::new(revision.revision_date);
+            let is_current = revision.revision_uuid == post.revision_uuid;
+            let diff_url = revisions.get(i + 1)
+                .map(|previous| diff_url(&post, &previous.revision_uuid, &revision.revision_uuid))
+                .unwrap_or_default();
+            PostRevisionItem {
+                revision_uuid: revision.revision_uuid.clone(),
+                url: if is_current { post.url() } else { revision_url(&post, &revision.revision_uuid) },
+                title: revision.title.clone(),
+                date: date.default_format_in_timezone(timezone),
+                date_value: date.to_utc_datetime_string(),
+                is_current,
+                diff_url,
+            }
+        }).collect();
+
+        let content_template = ContentPostRevisionsTemplate {
+            title: post.title.clone(),
+            post_url: post.url(),
+            channel_lang: post.channel.lang.clone(),
+            dir: TextDirection::detect(&post.channel.lang, &post.title).to_string(),
+            revisions,
+            diff_url: format!("{}diff/", post.url()),
+        };
+
+        let template = BaseTemplate::try_new(
+            &url,
+            Some(&format!("Revisions - {}", post.title)),
+            &content_template.render()?,
+            &config,
+        )?;
+
+        Ok(HtmlTemplate(template).into_response())
+    }).await
+}
+
+pub async fn handler_post_revision(
+    Path((channel_handle, post_uuid, revision_uuid)): Path<(String, String, String)>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let backend_api = BackendApi::new_v1(&config);
+        let current = match backend_api.get_post(&post_uuid).await? {
+            Some(post) if post.channel.handle == channel_handle => post,
+            _ => return Ok(handler_404(request).await.into_response()),
+        };
+        if revision_uuid == current.revision_uuid {
+            return Ok((StatusCode::FOUND, [(header::LOCATION, current.url())]).into_response());
+        }
+        let post = if let Some(post) = backend_api.get_post_revision(&current.post_uuid, &revision_uuid).await? {
+            post
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+
+        let tag_html_list = post.tags.iter().map(|tag_name| {
+            let content_template = ContentTagListItemTemplate::new(tag_name);
+            content_template.render()
+        }).collect::<Result<Vec<String>, askama::Error>>()?.join("\n");
+
+        let revision_date = UnixTime::new(post.revision_date);
+        let notice_template = ContentPostRevisionNoticeTemplate {
+            date: revision_date.default_format_in_timezone(config.server_timezone()),
+            date_value: revision_date.to_utc_datetime_string(),
+            post_url: current.url(),
+            revisions_url: format!("{}revisions/", current.url()),
+        };
+
+        let dir = TextDirection::detect(&post.channel.lang, &post.title);
+        let title_dir = TextDirection::from_text(&post.title).unwrap_or(dir);
+        let content_template = ContentPostTemplate {
+            post_uuid: post.post_uuid.clone(),
+            title: post.title.clone(),
+            date: revision_date.default_format_in_timezone(config.server_timezone()),
+            date_value: revision_date.to_utc_datetime_string(),
+            date_alt: config.secondary_date_string(&post.channel.lang, revision_date),
+            channel_handle: post.channel.handle.clone(),
+            channel_name: post.channel.name.clone(),
+            channel_lang: post.channel.lang.clone(),
+            dir: dir.to_string(),
+            title_dir: title_dir.to_string(),
+            content_html: markdown::to_html(&post.revision_text),
+            author_uuid: post.author.uuid.clone(),
+            author_name: post.author.name.clone(),
+            tag_list_html: tag_html_list,
+            related_post_list_html: String::new(),
+            series_html: String::new(),
+            revision_notice_html: notice_template.render()?,
+            citation: String::new(),
+            older_post_url: String::new(),
+            older_post_title: String::new(),
+            newer_post_url: String::new(),
+            newer_post_title: String::new(),
+        };
+
+        let template = BaseTemplate::try_new(
+            &url,
+            Some(post.title.as_str()),
+            &content_template.render()?,
+            &config,
+        )?.with_canonical_url(&current.url())?;
+
+        Ok(HtmlTemplate(template).into_response())
+    }).await
+}
+
+/// Changes between two revisions, by default from the revision before `to`,
+/// which defaults to the current one.
+pub async fn handler_post_diff(
+    Path((channel_handle, post_uuid)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let backend_api = BackendApi::new_v1(&config);
+        let current = match backend_api.get_post(&post_uuid).await? {
+            Some(post) if post.channel.handle == channel_handle => post,
+            _ => return Ok(handler_404(request).await.into_response()),
+        };
+
+        let to_revision_uuid = params.get("to").cloned().unwrap_or_else(|| current.revision_uuid.clone());
+        let from_revision_uuid = match params.get("from") {
+            Some(from) => from.clone(),
+            None => {
+                let mut revisions = backend_api.get_post_revisions(&current.post_uuid).await?.unwrap_or_default();
+                revisions.sort_by_key(|revision| std::cmp::Reverse(revision.revision_date));
+                let previous = revisions.iter()
+                    .position(|revision| revision.revision_uuid == to_revision_uuid)
+                    .and_then(|i| revisions.get(i + 1));
+                match previous {
+                    Some(previous) => previous.revision_uuid.clone(),
+                    None => return Ok(handler_404(request).await.into_response()),
+                }
+            },
+        };
+
+        let from = backend_api.get_post_revision(&current.post_uuid, &from_revision_uuid).await?;
+        let to = backend_api.get_post_revision(&current.post_uuid, &to_revision_uuid).await?;
+        let (from, to) = match (from, to) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Ok(handler_404(request).await.into_response()),
+        };
+
+        let revision_page_url = |post: &PostInfo| if post.revision_uuid == current.revision_uuid {
+            current.url()
+        } else {
+            revision_url(&current, &post.revision_uuid)
+        };
+        let timezone = config.server_timezone();
+        let from_date = UnixTime::new(from.revision_date);
+        let to_date = UnixTime::new(to.revision_date);
+        let content_template = ContentPostDiffTemplate {
+            title_html: diff::to_inline_html(&from.title, &to.title),
+            post_url: current.url(),
+            revisions_url: format!("{}revisions/", current.url()),
+            channel_lang: current.channel.lang.clone(),
+            dir: TextDirection::detect(&current.channel.lang, &current.title).to_string(),
+            from_url: revision_page_url(&from),
+            from_date: from_date.default_format_in_timezone(timezone),
+            from_date_value: from_date.to_utc_datetime_string(),
+            to_url: revision_page_url(&to),
+            to_date: to_date.default_format_in_timezone(timezone),
+            to_date_value: to_date.to_utc_datetime_string(),
+            is_identical: from.revision_text == to.revision_text,
+            rendered_html: diff::to_html(&from.revision_text, &to.revision_text),
+            hunks: diff::unified_hunks(&from.revision_text, &to.revision_text),
+        };
+
+        let template = BaseTemplate::try_new(
+            &url,
+            Some(&format!("Changes - {}", current.title)),
+            &content_template.render()?,
+            &config,
+        )?;
+
+        Ok(HtmlTemplate(template).into_response())
+    }).await
+}
@@ -0,0 +1,138 @@
+//! Full-text search page and OpenSearch description.
+
+use std::collections::HashMap;
+
+use axum::response::IntoResponse;
+use axum::extract::Query;
+use axum::http::{Request, header};
+use axum::body::Body;
+
+use askama::Template;
+use url::Url;
+
+use crate::error_reporting::result_into_response;
+use crate::config;
+use crate::template::{
+    HtmlTemplate,
+    BaseTemplate,
+    ContentSearchTemplate,
+    ContentSearchResultItemTemplate,
+    ContentSingleParagraphMessageTemplate,
+    OpenSearchDescriptionTemplate,
+};
+use crate::search::{search_index, DocumentKind};
+use crate::unix_time::UnixTime;
+use crate::lang::TextDirection;
+
+
+const SEARCH_RESULTS_PER_PAGE: usize = 20;
+
+/// Pages beyond this are clamped, keeping the offset arithmetic in range.
+const MAX_SEARCH_PAGE: usize = 10000;
+
+fn search_page_url(query: &str, page: usize) -> String {
+    format!("/search/?q={}&page={}", urlencoding::encode(query), page)
+}
+
+pub async fn handler_search(
+    Query(params): Query<HashMap<String, String>>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let query = params.get("q").map(|q| q.trim().to_string()).unwrap_or_default();
+        let page = params.get("page").and_then(|page| page.parse::<usize>().ok()).unwrap_or(1).clamp(1, MAX_SEARCH_PAGE);
+
+        let (results, index_is_empty) = {
+            let index = search_index().read().unwrap();
+            let results = index.search(&query, (page - 1) * SEARCH_RESULTS_PER_PAGE, SEARCH_RESULTS_PER_PAGE);
+            (results, index.is_empty())
+        };
+
+        let mut html = String::new();
+        if !query.is_empty() && results.hits.is_empty() {
+            let message = if index_is_empty {
+                "The search index is being built. Please try again later."
+            } else {
+                "There is no result for this query."
+            };
+            let content_template = ContentSingleParagraphMessageTemplate {
+                message: message.to_string(),
+            };
+            html.push_str(&content_template.render()?);
+        }
+        for hit in &results.hits {
+            let document = &hit.document;
+            let date = UnixTime::new(document.date);
+            let content_template = ContentSearchResultItemTemplate {
+                kind: document.kind.label().to_string(),
+                url: document.url.clone(),
+                title: document.title.clone(),
+                lang: document.lang.clone(),
+                dir: TextDirection::detect(&document.lang, &document.title).to_string(),
+                date: if document.kind == DocumentKind::Post {
+                    date.default_format_in_timezone(config.server_timezone())
+                } else {
+                    "".to_string()
+                },
+                date_value: date.to_utc_datetime_string(),
+                snippet_html: hit.snippet_html.clone(),
+            };
+            html.push_str(&content_template.render()?);
+        }
+
+        let prev_url = if page > 1 && !query.is_empty() {
+            search_page_url(&query, page - 1)
+        } else {
+            "".to_string()
+        };
+        let next_url = if page * SEARCH_RESULTS_PER_PAGE < results.total {
+            search_page_url(&query, page + 1)
+        } else {
+            "".to_string()
+        };
+
+        let content_template = ContentSearchTemplate {
+            query: query.clone(),
+            result_count: results.total,
+            result_list_html: html,
+            prev_url,
+            next_url,
+        };
+
+        let title = if query.is_empty() {
+            "Search".to_string()
+        } else {
+            format!("Search: {}", query)
+        };
+        let template = BaseTemplate::try_new(
+            &url,
+            Some(&title),
+            &content_template.render()?,
+            &config,
+        )?;
+
+        Ok(HtmlTemplate(template))
+    }).await
+}
+
+pub async fn handler_opensearch() -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let top_url = Url::parse(&config.top_url)?;
+
+        let template = OpenSearchDescriptionTemplate {
+            short_name: config.site_name.chars().take(16).collect(),
+            description: config.site_description.clone(),
+            search_url: top_url.join("/search/")?.to_string(),
+            icon_url: top_url.join("/branding/favicon.svg")?.to_string(),
+        };
+
+        Ok((
+            [(header::CONTENT_TYPE, "application/opensearchdescription+xml; charset=utf-8")],
+            template.render()?,
+        ))
+    }).await
+}
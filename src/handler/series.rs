@@ -0,0 +1,228 @@
+//! Series of posts, defined by tags with the series prefix or listed in the config.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use axum::response::IntoResponse;
+use axum::extract::Path;
+use axum::http::Request;
+use axum::body::Body;
+
+use askama::Template;
+
+use crate::error_reporting::result_into_response;
+use crate::config::{self, Config};
+use crate::template::{
+    HtmlTemplate,
+    BaseTemplate,
+    ContentSeriesTemplate,
+    ContentSeriesNavTemplate,
+    SeriesNavPart,
+};
+use crate::backend_api::BackendApi;
+use crate::cache::TtlCache;
+
+use super::{
+    PostSummary,
+    PostInfo,
+    fetch_posts,
+    render_post_list_items,
+    handler_404,
+};
+
+
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+const CACHE_CAPACITY: usize = 256;
+
+// post uuid -> date of the first revision, which never changes
+const FIRST_REVISION_CACHE_TTL: Duration = Duration::from_secs(86400);
+const FIRST_REVISION_CACHE_CAPACITY: usize = 4096;
+
+
+#[derive(Debug, Clone)]
+pub(super) struct Series {
+    pub name: String,
+    pub title: String,
+    pub parts: Vec<PostSummary>, // in reading order
+}
+
+impl Series {
+    pub fn url(&self) -> String {
+        format!("/series/{}/", urlencoding::encode(&self.name))
+    }
+}
+
+// series name -> series, or `None` if there is no such series
+static SERIES_CACHE: OnceLock<TtlCache<Option<Arc<Series>>>> = OnceLock::new();
+
+fn cache() -> &'static TtlCache<Option<Arc<Series>>> {
+    SERIES_CACHE.get_or_init(|| TtlCache::new(CACHE_TTL, CACHE_CAPACITY))
+}
+
+static FIRST_REVISION_CACHE: OnceLock<TtlCache<u64>> = OnceLock::new();
+
+fn first_revision_cache() -> &'static TtlCache<u64> {
+    FIRST_REVISION_CACHE.get_or_init(|| TtlCache::new(FIRST_REVISION_CACHE_TTL, FIRST_REVISION_CACHE_CAPACITY))
+}
+
+/// Date of the first revision of the post, or `None` if there is no such post.
+pub(super) async fn try_first_revision_date(backend_api: &BackendApi, post_uuid: &str) -> Result<Option<u64>, anyhow::Error> {
+    if let Some(date) = first_revision_cache().get(post_uuid) {
+        return Ok(Some(date));
+    }
+    let date = backend_api.get_post_revisions(post_uuid).await?
+        .and_then(|revisions| revisions.iter().map(|revision| revision.revision_date).min());
+    if let Some(date) = date {
+        first_revision_cache().insert(post_uuid, date);
+    }
+    Ok(date)
+}
+
+/// Date of the first revision of the post, or of the current one if the history is unavailable.
+async fn first_revision_date(backend_api: &BackendApi, post: &PostSummary) -> u64 {
+    try_first_revision_date(backend_api, &post.post_uuid).await.ok().flatten().unwrap_or(post.revision_date)
+}
+
+/// Sorts the posts by when they were first published, so that editing a post does not move it.
+async fn sort_by_first_revision(backend_api: &BackendApi, posts: &mut [PostSummary]) {
+    let mut dates = HashMap::new();
+    for post in posts.iter() {
+        dates.insert(post.post_uuid.clone(), first_revision_date(backend_api, post).await);
+    }
+    posts.sort_by(|a, b| {
+        dates[&a.post_uuid].cmp(&dates[&b.post_uuid]).then_with(|| a.title.cmp(&b.title))
+    });
+}
+
+fn post_url(post: &PostSummary) -> String {
+    match &post.channel {
+        Some(channel) => format!("/c/{}/{}/", channel.handle, post.post_uuid),
+        None => "".to_string(),
+    }
+}
+
+/// Names of the series the post belongs to, configured ones first.
+fn series_names(post: &PostInfo, config: &Config) -> Vec<String> {
+    let mut names: Vec<String> = config.series.iter()
+        .filter(|series| series.posts.contains(&post.post_uuid))
+        .map(|series| series.name.clone())
+        .collect();
+    if !config.series_tag_prefix.is_empty() {
+        for tag_name in &post.tags {
+            let name = match tag_name.strip_prefix(&config.series_tag_prefix) {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => continue,
+            };
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// Configured series in the listed order, or tagged posts from the first published.
+async fn fetch_series(name: &str, config: &Config) -> Result<Option<Series>, anyhow::Error> {
+    let backend_api = BackendApi::new_v1(config);
+
+    if let Some(definition) = config.series.iter().find(|series| series.name == name) {
+        return Ok(Some(Series {
+            name: definition.name.clone(),
+            title: definition.title.clone(),
+            parts: fetch_posts(&backend_api, &definition.posts).await?,
+        }));
+    }
+
+    if config.series_tag_prefix.is_empty() {
+        return Ok(None);
+    }
+    let mut query = HashMap::new();
+    query.insert("tag_name".to_string(), format!("{}{}", config.series_tag_prefix, name));
+    let bytes = backend_api.get_bytes("tag/posts", query).await?;
+    let mut parts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+    if parts.is_empty() {
+        return Ok(None);
+    }
+    sort_by_first_revision(&backend_api, &mut parts).await;
+    Ok(Some(Series {
+        name: name.to_string(),
+        title: name.to_string(),
+        parts,
+    }))
+}
+
+async fn series(name: &str, config: &Config) -> Result<Option<Arc<Series>>, anyhow::Error> {
+    if let Some(series) = cache().get(name) {
+        return Ok(series);
+    }
+    let series = fetch_series(name, config).await?.map(Arc::new);
+    cache().insert(name, series.clone());
+    Ok(series)
+}
+
+/// Navigators for every series the post belongs to.
+pub(super) async fn render_series_navigation(post: &PostInfo, config: &Config) -> Result<String, anyhow::Error> {
+    let mut html = String::new();
+    for name in series_names(post, config) {
+        let series = match series(&name, config).await? {
+            Some(series) => series,
+            None => continue,
+        };
+        let position = match series.parts.iter().position(|part| part.post_uuid == post.post_uuid) {
+            Some(index) => index + 1,
+            None => continue,
+        };
+        let parts = series.parts.iter().enumerate().map(|(i, part)| SeriesNavPart {
+            number: i + 1,
+            url: post_url(part),
+            title: part.title.clone(),
+            is_current: part.post_uuid == post.post_uuid,
+        }).collect();
+        let content_template = ContentSeriesNavTemplate {
+            series_title: series.title.clone(),
+            series_url: series.url(),
+            position,
+            part_count: series.parts.len(),
+            parts,
+        };
+        html.push_str(&content_template.render()?);
+    }
+    Ok(html)
+}
+
+pub async fn handler_series(
+    Path(series_name): Path<String>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let series = if let Some(series) = series(&series_name, &config).await? {
+            series
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+
+        let parts = series.parts.iter()
+            .map(|part| render_post_list_items(std::slice::from_ref(part), &config))
+            .collect::<Result<Vec<String>, anyhow::Error>>()?;
+
+        let content_template = ContentSeriesTemplate {
+            series_title: series.title.clone(),
+            part_count: parts.len(),
+            part_html_list: parts,
+        };
+
+        let template = BaseTemplate::try_new(
+            &url,
+            Some(&format!("Series: {}", series.title)),
+            &content_template.render()?,
+            &config,
+        )?;
+
+        Ok(HtmlTemplate(template).into_response())
+    }).await
+}
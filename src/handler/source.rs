@@ -0,0 +1,108 @@
+//! Source text of posts, as Markdown with front matter and as plain text.
+
+use axum::response::IntoResponse;
+use axum::extract::Path;
+use axum::http::{Request, header};
+use axum::body::Body;
+
+use serde_json::Value;
+
+use url::Url;
+
+use crate::error_reporting::result_into_response;
+use crate::config::{self, Config};
+use crate::unix_time::UnixTime;
+use crate::backend_api::BackendApi;
+use crate::markdown;
+use crate::front_matter;
+
+use super::{
+    PostInfo,
+    handler_404,
+};
+
+
+/// Front matter of the post, with the date as RFC 3339 in server_timezone.
+fn post_front_matter(post: &PostInfo, canonical_url: &str, config: &Config) -> String {
+    front_matter::to_string(&[
+        ("title", Value::from(post.title.as_str())),
+        ("author", Value::from(post.author.name.as_str())),
+        ("author_uuid", Value::from(post.author.uuid.as_str())),
+        ("channel", Value::from(post.channel.name.as_str())),
+        ("channel_handle", Value::from(post.channel.handle.as_str())),
+        ("lang", Value::from(post.channel.lang.as_str())),
+        ("tags", Value::from(post.tags.clone())),
+        ("date", Value::from(UnixTime::new(post.revision_date).to_datetime(config.server_timezone()).to_rfc3339())),
+        ("post_uuid", Value::from(post.post_uuid.as_str())),
+        ("revision_uuid", Value::from(post.revision_uuid.as_str())),
+        ("url", Value::from(canonical_url)),
+    ])
+}
+
+fn canonical_url(post: &PostInfo, config: &Config) -> Result<String, anyhow::Error> {
+    Ok(Url::parse(&config.top_url)?.join(&post.url())?.to_string())
+}
+
+pub async fn handler_post_markdown(
+    Path((channel_handle, post_uuid)): Path<(String, String)>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+
+        let backend_api = BackendApi::new_v1(&config);
+        let post = match backend_api.get_post(&post_uuid).await? {
+            Some(post) if post.channel.handle == channel_handle => post,
+            _ => return Ok(handler_404(request).await.into_response()),
+        };
+
+        let canonical_url = canonical_url(&post, &config)?;
+        let front_matter = post_front_matter(&post, &canonical_url, &config);
+        let mut text = format!("---\n{}---\n\n", front_matter);
+        text.push_str(post.revision_text.trim_end());
+        text.push('\n');
+
+        Ok((
+            [
+                (header::CONTENT_TYPE, "text/markdown; charset=utf-8".to_string()),
+                (header::LINK, format!("<{}>; rel=\"canonical\"", canonical_url)),
+            ],
+            text,
+        ).into_response())
+    }).await
+}
+
+pub async fn handler_post_plain_text(
+    Path((channel_handle, post_uuid)): Path<(String, String)>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+
+        let backend_api = BackendApi::new_v1(&config);
+        let post = match backend_api.get_post(&post_uuid).await? {
+            Some(post) if post.channel.handle == channel_handle => post,
+            _ => return Ok(handler_404(request).await.into_response()),
+        };
+
+        let canonical_url = canonical_url(&post, &config)?;
+        let date = UnixTime::new(post.revision_date).default_format_in_timezone(config.server_timezone());
+        let text = format!(
+            "{}\n{} / {}\n{}\n{}\n\n{}\n",
+            post.title,
+            post.author.name,
+            post.channel.name,
+            date,
+            canonical_url,
+            markdown::to_plain_text(&post.revision_text),
+        );
+
+        Ok((
+            [
+                (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+                (header::LINK, format!("<{}>; rel=\"canonical\"", canonical_url)),
+            ],
+            text,
+        ).into_response())
+    }).await
+}
@@ -0,0 +1,326 @@
+//! Posting statistics of channels and authors, with an SVG activity heatmap.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use axum::response::{IntoResponse, Response};
+use axum::extract::{Path, Query};
+use axum::http::Request;
+use axum::body::Body;
+
+use askama::Template;
+
+use chrono::{Datelike, Days, NaiveDate};
+
+use crate::error_reporting::result_into_response;
+use crate::config::{self, Config};
+use crate::template::{
+    HtmlTemplate,
+    BaseTemplate,
+    ContentStatsTemplate,
+    StatsTag,
+    StatsYear,
+    Heatmap,
+    HeatmapCell,
+    HeatmapLabel,
+};
+use crate::unix_time::UnixTime;
+use crate::backend_api::BackendApi;
+use crate::cache::TtlCache;
+use crate::search::search_index;
+
+use super::{
+    PostSummary,
+    PostInfo,
+    ChannelInfo,
+    AuthorInfo,
+    handler_404,
+};
+
+
+/// At most this many posts per page view are fetched for their tags;
+/// the rest come from the search index or the cache.
+const MAX_TAG_FETCHES: usize = 100;
+
+/// Number of tags shown in the distribution.
+const MAX_TAGS: usize = 20;
+
+// revision uuid -> tags
+const TAG_CACHE_TTL: Duration = Duration::from_secs(86400);
+const TAG_CACHE_CAPACITY: usize = 4096;
+
+// heatmap geometry
+const CELL_SIZE: u32 = 11;
+const CELL_GAP: u32 = 2;
+const LABEL_WIDTH: u32 = 28;
+const LABEL_HEIGHT: u32 = 16;
+
+/// Fill colors from no posts to the most active days.
+const HEATMAP_COLORS: [&str; 5] = ["#ebedf0", "#c6e48b", "#7bc96f", "#239a3b", "#196127"];
+
+const WEEKDAY_LABELS: [(u32, &str); 3] = [(0, "Mon"), (2, "Wed"), (4, "Fri")];
+
+
+static TAG_CACHE: OnceLock<TtlCache<Vec<String>>> = OnceLock::new();
+
+fn tag_cache() -> &'static TtlCache<Vec<String>> {
+    TAG_CACHE.get_or_init(|| TtlCache::new(TAG_CACHE_TTL, TAG_CACHE_CAPACITY))
+}
+
+/// Number of posts with each tag. Also returns whether every post was counted.
+async fn tag_counts(posts: &[PostSummary], config: &Config) -> Result<(BTreeMap<String, usize>, bool), anyhow::Error> {
+    let backend_api = BackendApi::new_v1(config);
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut fetches = 0;
+    let mut is_complete = true;
+    for post in posts {
+        let channel_handle = post.channel.as_ref().map(|channel| channel.handle.as_str()).unwrap_or_default();
+        let url = format!("/c/{}/{}/", channel_handle, post.post_uuid);
+        let indexed = search_index().read().unwrap().tags(&url, &post.revision_uuid);
+        let tags = match indexed.or_else(|| tag_cache().get(&post.revision_uuid)) {
+            Some(tags) => tags,
+            None if fetches < MAX_TAG_FETCHES => {
+                fetches += 1;
+                let mut query = HashMap::new();
+                query.insert("uuid".to_string(), post.post_uuid.clone());
+                let bytes = backend_api.get_bytes("post/info", query).await?;
+                let info: PostInfo = serde_json::from_slice(&bytes)?;
+                tag_cache().insert(&post.revision_uuid, info.tags.clone());
+                info.tags
+            },
+            None => {
+                is_complete = false;
+                continue;
+            },
+        };
+        for tag in tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+    Ok((counts, is_complete))
+}
+
+/// Heatmap of posts per day for the year, or for the year up to `today`.
+fn heatmap(post_dates: &[NaiveDate], year: Option<i32>, today: NaiveDate) -> Heatmap {
+    let (start, end) = match year {
+        Some(year) => (
+            NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(year, 12, 31).unwrap(),
+        ),
+        None => (today - Days::new(364), today),
+    };
+
+    let mut counts: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for date in post_dates.iter().filter(|date| (start..=end).contains(date)) {
+        *counts.entry(*date).or_default() += 1;
+    }
+    let max_count = counts.values().copied().max().unwrap_or(0);
+
+    // columns are weeks from Monday
+    let first_monday = start - Days::new(start.weekday().num_days_from_monday() as u64);
+    let mut cells = Vec::new();
+    let mut month_labels = Vec::new();
+    let mut date = start;
+    while date <= end {
+        let week = ((date - first_monday).num_days() / 7) as u32;
+        let weekday = date.weekday().num_days_from_monday();
+        let x = LABEL_WIDTH + week * (CELL_SIZE + CELL_GAP);
+        let y = LABEL_HEIGHT + weekday * (CELL_SIZE + CELL_GAP);
+
+        let count = counts.get(&date).copied().unwrap_or(0);
+        let levels = HEATMAP_COLORS.len() - 1;
+        let level = if count == 0 {
+            0
+        } else if max_count <= 1 {
+            1
+        } else {
+            1 + (count - 1) * (levels - 1) / (max_count - 1)
+        };
+        cells.push(HeatmapCell {
+            x,
+            y,
+            size: CELL_SIZE,
+            fill: HEATMAP_COLORS[level].to_string(),
+            level,
+            title: format!("{}: {} {}", date.format("%Y-%m-%d"), count, if count == 1 { "post" } else { "posts" }),
+        });
+
+        if date.day() == 1 || date == start {
+            month_labels.push(HeatmapLabel {
+                x,
+                y: LABEL_HEIGHT - 4,
+                text: date.format("%b").to_string(),
+            });
+        }
+        date = date + Days::new(1);
+    }
+    // drop a label squeezed in by a partial first month
+    if month_labels.len() > 1 && month_labels[1].x - month_labels[0].x < 3 * (CELL_SIZE + CELL_GAP) {
+        month_labels.remove(0);
+    }
+
+    let weekday_labels = WEEKDAY_LABELS.iter().map(|(weekday, text)| HeatmapLabel {
+        x: 0,
+        y: LABEL_HEIGHT + weekday * (CELL_SIZE + CELL_GAP) + CELL_SIZE - 1,
+        text: text.to_string(),
+    }).collect();
+
+    let weeks = ((end - first_monday).num_days() / 7 + 1) as u32;
+    Heatmap {
+        width: LABEL_WIDTH + weeks * (CELL_SIZE + CELL_GAP),
+        height: LABEL_HEIGHT + 7 * (CELL_SIZE + CELL_GAP),
+        label: match year {
+            Some(year) => format!("Posts per day in {}", year),
+            None => "Posts per day in the last year".to_string(),
+        },
+        cells,
+        month_labels,
+        weekday_labels,
+    }
+}
+
+struct StatsSubject {
+    title: String, // channel or author name
+    url: String, // of the channel or the author
+    stats_url: String,
+}
+
+async fn render_stats(
+    url: &str,
+    config: &Config,
+    subject: StatsSubject,
+    posts: Vec<PostSummary>,
+    params: &HashMap<String, String>,
+) -> Result<Response, anyhow::Error> {
+    let timezone = config.server_timezone();
+    let post_dates: Vec<NaiveDate> = posts.iter()
+        .map(|post| UnixTime::new(post.revision_date).to_datetime(timezone).date_naive())
+        .collect();
+    let today = UnixTime::now().to_datetime(timezone).date_naive();
+
+    let mut years: Vec<i32> = post_dates.iter().map(|date| date.year()).collect();
+    years.sort();
+    years.dedup();
+    let year = params.get("year").and_then(|year| year.parse::<i32>().ok()).filter(|year| years.contains(year));
+
+    let first_date = posts.iter().map(|post| post.revision_date).min();
+    let latest_date = posts.iter().map(|post| post.revision_date).max();
+
+    let (counts, tags_complete) = tag_counts(&posts, config).await?;
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let max_tag_count = counts.first().map(|(_, count)| *count).unwrap_or(0);
+    let tags = counts.into_iter().take(MAX_TAGS).map(|(tag_name, count)| StatsTag {
+        tag_name_urlencoded: urlencoding::encode(&tag_name).to_string(),
+        tag_name,
+        count,
+    }).collect();
+
+    let stats_years = years.iter().rev().map(|other| StatsYear {
+        label: other.to_string(),
+        url: format!("{}?year={}", subject.stats_url, other),
+        is_current: year == Some(*other),
+    }).collect();
+
+    let format_date = |date: Option<u64>| date
+        .map(|date| UnixTime::new(date).default_format_in_timezone(timezone))
+        .unwrap_or_default();
+    let content_template = ContentStatsTemplate {
+        subject_title: subject.title.clone(),
+        subject_url: subject.url,
+        stats_url: subject.stats_url,
+        post_count: posts.len(),
+        first_date: format_date(first_date),
+        first_date_value: first_date.map(|date| UnixTime::new(date).to_utc_datetime_string()).unwrap_or_default(),
+        latest_date: format_date(latest_date),
+        latest_date_value: latest_date.map(|date| UnixTime::new(date).to_utc_datetime_string()).unwrap_or_default(),
+        tags,
+        max_tag_count,
+        tags_complete,
+        years: stats_years,
+        is_last_year: year.is_none(),
+        heatmap: heatmap(&post_dates, year, today),
+    };
+
+    let template = BaseTemplate::try_new(
+        url,
+        Some(&format!("Statistics - {}", subject.title)),
+        &content_template.render()?,
+        config,
+    )?;
+
+    Ok(HtmlTemplate(template).into_response())
+}
+
+pub async fn handler_channel_stats(
+    Path(channel_handle): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let mut query = HashMap::new();
+        query.insert("handle".to_string(), channel_handle);
+        let backend_api = BackendApi::new_v1(&config);
+        let bytes = if let Ok(bytes) = backend_api.get_bytes("channel/info", query).await {
+            bytes
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+        let channel: ChannelInfo = serde_json::from_slice(&bytes)?;
+
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), channel.uuid.to_string());
+        let bytes = backend_api.get_bytes("channel/posts", query).await?;
+        let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+        let posts: Vec<PostSummary> = posts.into_iter()
+            .map(|post| post.with_channel(channel.summary()))
+            .collect();
+
+        let subject = StatsSubject {
+            title: channel.name.clone(),
+            url: format!("/c/{}/", channel.handle),
+            stats_url: format!("/c/{}/stats/", channel.handle),
+        };
+        render_stats(&url, &config, subject, posts, &params).await
+    }).await
+}
+
+pub async fn handler_author_stats(
+    Path(author_uuid): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    request: Request<Body>,
+) -> impl IntoResponse {
+    result_into_response(async move {
+        let config = config::load_config().await;
+        let url = request.uri().path().to_string();
+
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), author_uuid);
+        let backend_api = BackendApi::new_v1(&config);
+        let bytes = if let Ok(bytes) = backend_api.get_bytes("author/info", query).await {
+            bytes
+        } else {
+            return Ok(handler_404(request).await.into_response());
+        };
+        let author: AuthorInfo = serde_json::from_slice(&bytes)?;
+
+        let mut query = HashMap::new();
+        query.insert("uuid".to_string(), author.uuid.to_string());
+        let bytes = backend_api.get_bytes("author/posts", query).await?;
+        let posts: Vec<PostSummary> = serde_json::from_slice(&bytes)?;
+        let posts: Vec<PostSummary> = posts.into_iter()
+            .map(|post| post.with_author(author.summary()))
+            .collect();
+
+        let subject = StatsSubject {
+            title: author.name.clone(),
+            url: format!("/authors/{}/", author.uuid),
+            stats_url: format!("/authors/{}/stats/", author.uuid),
+        };
+        render_stats(&url, &config, subject, posts, &params).await
+    }).await
+}
@@ -0,0 +1,207 @@
+//! Helpers for BCP 47 language tags, script direction and collation.
+
+use std::fmt::{
+    self,
+    Display,
+    Formatter,
+};
+
+use serde::{Serialize, Deserialize};
+
+use icu_collator::{Collator, CollatorOptions};
+use icu_locid::Locale;
+
+
+/// Scripts written from right to left (ISO 15924, lowercased).
+const RTL_SCRIPTS: &[&str] = &[
+    "adlm", "arab", "aran", "hebr", "mand", "mend", "nkoo",
+    "rohg", "samr", "syrc", "thaa", "yezi",
+];
+
+/// Languages whose default script is written from right to left.
+const RTL_LANGUAGES: &[&str] = &[
+    "ar", "arc", "ckb", "dv", "fa", "glk", "he", "iw", "ji", "ks",
+    "lrc", "mzn", "pnb", "ps", "sd", "syr", "ug", "ur", "yi",
+];
+
+/// Primary subtags that do not denote a particular language.
+const NON_LANGUAGES: &[&str] = &["und", "mul", "zxx", "mis"];
+
+
+/// Subtags of a BCP 47 language tag relevant for matching and direction.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LangTag {
+    subtags: Vec<String>, // lowercased
+}
+
+impl LangTag {
+    /// Parses a language tag, accepting `_` as a separator as well.
+    pub fn parse(tag: &str) -> Option<Self> {
+        let subtags: Vec<String> = tag.trim()
+            .split(['-', '_'])
+            .map(|subtag| subtag.to_ascii_lowercase())
+            .collect();
+
+        let primary = subtags.first()?;
+        if primary.len() < 2 || primary.len() > 8 || !primary.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        if subtags.iter().any(|subtag| subtag.is_empty() || !subtag.chars().all(|c| c.is_ascii_alphanumeric())) {
+            return None;
+        }
+
+        Some(Self {
+            subtags,
+        })
+    }
+
+    pub fn primary_language(&self) -> &str {
+        &self.subtags[0]
+    }
+
+    /// Script subtag, if given explicitly.
+    pub fn script(&self) -> Option<&str> {
+        self.subtags.iter()
+            .skip(1)
+            .take_while(|subtag| subtag.len() != 1) // stop at extensions
+            .find(|subtag| subtag.len() == 4 && subtag.chars().all(|c| c.is_ascii_alphabetic()))
+            .map(|subtag| subtag.as_str())
+    }
+
+    pub fn direction(&self) -> Option<TextDirection> {
+        if let Some(script) = self.script() {
+            return Some(if RTL_SCRIPTS.contains(&script) {
+                TextDirection::Rtl
+            } else {
+                TextDirection::Ltr
+            });
+        }
+
+        let primary = self.primary_language();
+        if NON_LANGUAGES.contains(&primary) {
+            None
+        } else if RTL_LANGUAGES.contains(&primary) {
+            Some(TextDirection::Rtl)
+        } else {
+            Some(TextDirection::Ltr)
+        }
+    }
+}
+
+impl Display for LangTag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.subtags.join("-"))
+    }
+}
+
+/// Basic filtering (RFC 4647): `ja` matches `ja` and `ja-JP`, `*` matches everything.
+pub fn lang_matches(range: &str, tag: &str) -> bool {
+    let range = range.trim();
+    if range == "*" {
+        return true;
+    }
+    let (range, tag) = match (LangTag::parse(range), LangTag::parse(tag)) {
+        (Some(range), Some(tag)) => (range, tag),
+        _ => return false,
+    };
+    range.subtags.len() <= tag.subtags.len()
+        && range.subtags.iter().zip(tag.subtags.iter()).all(|(a, b)| a == b)
+}
+
+/// Name of the language in itself, for the most common primary language subtags.
+pub fn autonym(primary_language: &str) -> Option<&'static str> {
+    Some(match primary_language {
+        "ar" => "العربية",
+        "de" => "Deutsch",
+        "en" => "English",
+        "es" => "Español",
+        "fa" => "فارسی",
+        "fr" => "Français",
+        "he" => "עברית",
+        "hi" => "हिन्दी",
+        "id" => "Bahasa Indonesia",
+        "it" => "Italiano",
+        "ja" => "日本語",
+        "ko" => "한국어",
+        "ms" => "Bahasa Melayu",
+        "nl" => "Nederlands",
+        "pl" => "Polski",
+        "pt" => "Português",
+        "ru" => "Русский",
+        "sv" => "Svenska",
+        "th" => "ไทย",
+        "tr" => "Türkçe",
+        "uk" => "Українська",
+        "ur" => "اردو",
+        "vi" => "Tiếng Việt",
+        "zh" => "中文",
+        _ => return None,
+    })
+}
+
+/// Collator for sorting strings in the language. Falls back to the root collation.
+pub fn collator(lang: &str) -> Collator {
+    let locale: Locale = lang.parse().unwrap_or(Locale::UND);
+    Collator::try_new(&(&locale).into(), CollatorOptions::new())
+        .or_else(|_| Collator::try_new(&(&Locale::UND).into(), CollatorOptions::new()))
+        .expect("root collation data is compiled in")
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
+    Auto,
+}
+
+impl TextDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextDirection::Ltr => "ltr",
+            TextDirection::Rtl => "rtl",
+            TextDirection::Auto => "auto",
+        }
+    }
+
+    /// Direction implied by a language tag such as `ar`, `ja-JP` or `az-Arab`.
+    pub fn from_lang(lang: &str) -> Option<Self> {
+        LangTag::parse(lang)?.direction()
+    }
+
+    /// Direction of the first strong (letter) character in the text.
+    pub fn from_text(text: &str) -> Option<Self> {
+        text.chars()
+            .find(|c| c.is_alphabetic())
+            .map(|c| if is_rtl_char(c) {
+                TextDirection::Rtl
+            } else {
+                TextDirection::Ltr
+            })
+    }
+
+    /// Uses the language tag if it tells the direction, then the text, otherwise `auto`.
+    pub fn detect(lang: &str, text: &str) -> Self {
+        Self::from_lang(lang)
+            .or_else(|| Self::from_text(text))
+            .unwrap_or(TextDirection::Auto)
+    }
+}
+
+impl Display for TextDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Whether the character belongs to a block of right-to-left scripts.
+fn is_rtl_char(c: char) -> bool {
+    matches!(c as u32,
+        0x0590..=0x08FF // Hebrew, Arabic, Syriac, Thaana, NKo, Samaritan, Mandaic
+        | 0xFB1D..=0xFDFF // Hebrew and Arabic presentation forms
+        | 0xFE70..=0xFEFE // Arabic presentation forms-B
+        | 0x10800..=0x10FFF
+        | 0x1E800..=0x1EFFF
+    )
+}
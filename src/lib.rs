@@ -6,3 +6,16 @@ pub mod error_reporting;
 pub mod markdown;
 pub mod unix_time;
 pub mod backend_api;
+pub mod lang;
+pub mod calendar;
+pub mod search;
+pub mod cache;
+pub mod views;
+pub mod redirect;
+pub mod routes;
+pub mod front_matter;
+pub mod pages;
+pub mod navigation;
+pub mod diff;
+pub mod citation;
+pub mod epub;
@@ -1,6 +1,31 @@
 
-use comrak::{markdown_to_html, Options};
+use comrak::{markdown_to_html, parse_document, Arena, Options};
+use comrak::arena_tree::NodeEdge;
+use comrak::nodes::NodeValue;
 
 pub fn to_html(markdown: &str) -> String {
     markdown_to_html(markdown, &Options::default())
 }
+
+/// Text content of the Markdown document, with blocks separated by newlines.
+pub fn to_plain_text(markdown: &str) -> String {
+    let arena = Arena::new();
+    let root = parse_document(&arena, markdown, &Options::default());
+
+    let mut text = String::new();
+    for edge in root.traverse() {
+        match edge {
+            NodeEdge::Start(node) => match &node.data.borrow().value {
+                NodeValue::Text(literal) => text.push_str(literal),
+                NodeValue::Code(code) => text.push_str(&code.literal),
+                NodeValue::CodeBlock(code_block) => text.push_str(&code_block.literal),
+                NodeValue::SoftBreak | NodeValue::LineBreak => text.push(' '),
+                _ => {},
+            },
+            NodeEdge::End(node) => if node.data.borrow().value.block() && !text.ends_with('\n') {
+                text.push('\n');
+            },
+        }
+    }
+    text.trim().to_string()
+}
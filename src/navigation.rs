@@ -0,0 +1,141 @@
+//! Navigation menus, with links generated from backend data.
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use askama::Template;
+use url::Url;
+
+use crate::config::{self, Config};
+use crate::backend_api::BackendApi;
+use crate::template::{NavigationItem, NavigationSource, NavigationLink, NavigationSubmenuTemplate};
+use crate::handler::{ChannelSummary, MetaPageListItem};
+
+
+/// Links from the backend are refreshed this often.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+
+/// Links for each source, as of the last refresh.
+#[derive(Debug, Default)]
+struct SourceLinks {
+    meta_pages: Vec<NavigationItem>,
+    channels: Vec<NavigationItem>,
+}
+
+static SOURCE_LINKS: OnceLock<RwLock<SourceLinks>> = OnceLock::new();
+
+fn source_links() -> &'static RwLock<SourceLinks> {
+    SOURCE_LINKS.get_or_init(|| RwLock::new(SourceLinks::default()))
+}
+
+fn link(url: String, text: String) -> NavigationItem {
+    NavigationItem {
+        url,
+        text,
+        ..Default::default()
+    }
+}
+
+pub async fn refresh(config: &Config) -> Result<(), anyhow::Error> {
+    let backend_api = BackendApi::new_v1(config);
+
+    let bytes = backend_api.get_bytes("meta/list", HashMap::new()).await?;
+    let meta_pages: Vec<MetaPageListItem> = serde_json::from_slice(&bytes)?;
+    let meta_pages = meta_pages.into_iter()
+        .map(|meta_page| link(config.meta_page_url(&meta_page.page_name), meta_page.title))
+        .collect();
+
+    let bytes = backend_api.get_bytes("channel/list", HashMap::new()).await?;
+    let channels: Vec<ChannelSummary> = serde_json::from_slice(&bytes)?;
+    let channels = channels.into_iter()
+        .map(|channel| link(format!("/c/{}/", channel.handle), channel.name))
+        .collect();
+
+    *source_links().write().unwrap() = SourceLinks {
+        meta_pages,
+        channels,
+    };
+    Ok(())
+}
+
+/// Refreshes the links periodically. Runs forever.
+pub async fn run_refresher() {
+    loop {
+        let config = config::load_config().await;
+        if let Err(e) = refresh(&config).await {
+            log::warn!("Failed to refresh navigation: {}", e);
+        }
+        tokio::time::sleep(REFRESH_INTERVAL).await;
+    }
+}
+
+fn source_items(source: &NavigationSource, links: &SourceLinks) -> Vec<NavigationItem> {
+    match source {
+        NavigationSource::MetaPages => links.meta_pages.clone(),
+        NavigationSource::Channels { limit } => {
+            let limit = limit.unwrap_or(usize::MAX);
+            links.channels.iter().take(limit).cloned().collect()
+        },
+    }
+}
+
+/// Replaces sources with their links.
+fn expand(items: &[NavigationItem], links: &SourceLinks) -> Vec<NavigationItem> {
+    let mut expanded = Vec::new();
+    for item in items {
+        let generated = item.source.as_ref().map(|source| source_items(source, links)).unwrap_or_default();
+        if item.text.is_empty() && item.source.is_some() {
+            expanded.extend(generated);
+            continue;
+        }
+        let mut children = expand(&item.children, links);
+        children.extend(generated);
+        expanded.push(NavigationItem {
+            source: None,
+            children,
+            ..item.clone()
+        });
+    }
+    expanded
+}
+
+fn to_link(item: &NavigationItem, path: &str, top_url: &Url) -> NavigationLink {
+    let resolved = if item.url.is_empty() { None } else { top_url.join(&item.url).ok() };
+    let is_external = resolved.as_ref().map(|url| url.origin() != top_url.origin()).unwrap_or(false);
+    let is_current = !is_external && resolved.as_ref().map(|url| url.path() == path).unwrap_or(false);
+
+    let children: Vec<NavigationLink> = item.children.iter()
+        .map(|child| to_link(child, path, top_url))
+        .collect();
+    let current = if is_current {
+        "page"
+    } else if children.iter().any(|child| !child.current.is_empty()) {
+        "true"
+    } else {
+        ""
+    };
+    let submenu_html = if children.is_empty() {
+        String::new()
+    } else {
+        NavigationSubmenuTemplate { items: children }.render().unwrap_or_else(|e| {
+            log::warn!("Failed to render navigation submenu: {}", e);
+            String::new()
+        })
+    };
+
+    NavigationLink {
+        url: item.url.clone(),
+        text: item.text.clone(),
+        current: current.to_string(),
+        is_external,
+        submenu_html,
+    }
+}
+
+/// Links for the page at the path, with the current one marked.
+pub fn links(items: &[NavigationItem], path: &str, top_url: &Url) -> Vec<NavigationLink> {
+    let expanded = expand(items, &source_links().read().unwrap());
+    expanded.iter().map(|item| to_link(item, path, top_url)).collect()
+}
@@ -0,0 +1,200 @@
+//! Local Markdown pages, maintained in a content directory instead of the backend.
+//! Each file starts with front matter, one `key: value` per line:
+//!
+//! ```text
+//! ---
+//! title: Colophon
+//! date: 2024-04-01
+//! path: /colophon/
+//! navigation: footer
+//! ---
+//! ```
+
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use serde::{Serialize, Deserialize};
+
+use crate::template::NavigationItem;
+use crate::front_matter;
+use crate::routes;
+
+
+const DEFAULT_CONTENT_DIR: &str = "content";
+
+/// Changes to the directory show up after at most this time.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageNavigation {
+    Header,
+    #[default]
+    Footer,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FrontMatter {
+    title: String,
+
+    #[serde(default)]
+    date: String, // in server_timezone unless with an offset
+
+    #[serde(default)]
+    path: String, // defaults to /:file_stem/
+
+    #[serde(default)]
+    navigation: PageNavigation,
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalPage {
+    pub path: String,
+    pub title: String,
+    pub date: String,
+    pub navigation: PageNavigation,
+    pub text: String, // Markdown
+}
+
+impl LocalPage {
+    fn parse(file_path: &Path, source: &str) -> Result<Self, anyhow::Error> {
+        let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+        let rest = source.strip_prefix("---\n")
+            .or_else(|| source.strip_prefix("---\r\n"))
+            .ok_or_else(|| anyhow::anyhow!("Missing front matter"))?;
+        let end = rest.find("\n---")
+            .ok_or_else(|| anyhow::anyhow!("Unterminated front matter"))?;
+        let front_matter: FrontMatter = front_matter::from_str(&rest[..end])?;
+        let text = rest[end + 4..].split_once('\n').map(|(_, text)| text).unwrap_or("");
+
+        let path = if front_matter.path.is_empty() {
+            let stem = file_path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+            format!("/{}/", stem)
+        } else {
+            front_matter.path
+        };
+        if !path.starts_with('/') {
+            return Err(anyhow::anyhow!("Path must start with /: {}", path));
+        }
+
+        Ok(Self {
+            path,
+            title: front_matter.title,
+            date: front_matter.date,
+            navigation: front_matter.navigation,
+            text: text.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LocalPages {
+    pages: BTreeMap<String, LocalPage>, // path -> page
+    signature: Vec<(PathBuf, SystemTime, u64)>, // of the loaded files
+}
+
+impl LocalPages {
+    pub fn get(&self, path: &str) -> Option<&LocalPage> {
+        self.pages.get(path)
+    }
+
+    /// Links to the pages listed in the navigation, ordered by path.
+    pub fn navigation_items(&self, navigation: PageNavigation) -> Vec<NavigationItem> {
+        self.pages.values()
+            .filter(|page| page.navigation == navigation)
+            .map(|page| NavigationItem {
+                url: page.path.clone(),
+                text: page.title.clone(),
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+static LOCAL_PAGES: OnceLock<RwLock<LocalPages>> = OnceLock::new();
+
+pub fn local_pages() -> &'static RwLock<LocalPages> {
+    LOCAL_PAGES.get_or_init(|| RwLock::new(LocalPages::default()))
+}
+
+/// The directory is read from `CONTENT_DIR`.
+pub fn content_dir() -> PathBuf {
+    let dir = env::var("CONTENT_DIR").unwrap_or_default();
+    PathBuf::from(if dir.is_empty() { DEFAULT_CONTENT_DIR } else { &dir })
+}
+
+/// Markdown files in the directory with their modification times and sizes, sorted.
+fn scan(dir: &Path) -> Vec<(PathBuf, SystemTime, u64)> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    let mut files: Vec<(PathBuf, SystemTime, u64)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|extension| extension == "md").unwrap_or(false))
+        .filter_map(|path| {
+            let metadata = std::fs::metadata(&path).ok()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            Some((path, modified, metadata.len()))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Reloads the pages if any file was added, removed or modified.
+pub fn reload_if_changed(dir: &Path) {
+    let signature = scan(dir);
+    if local_pages().read().unwrap().signature == signature {
+        return;
+    }
+
+    let mut pages = BTreeMap::new();
+    for (file_path, _, _) in &signature {
+        let source = match std::fs::read_to_string(file_path) {
+            Ok(source) => source,
+            Err(e) => {
+                log::warn!("Failed to read {}: {}", file_path.display(), e);
+                continue;
+            },
+        };
+        match LocalPage::parse(file_path, &source) {
+            Ok(page) => {
+                if pages.contains_key(&page.path) {
+                    log::warn!("Duplicate local page path {} in {}", page.path, file_path.display());
+                    continue;
+                }
+                if routes::is_routed(&page.path) {
+                    log::warn!("Local page path {} in {} is handled by another route, ignored", page.path, file_path.display());
+                    continue;
+                }
+                pages.insert(page.path.clone(), page);
+            },
+            Err(e) => log::warn!("Invalid local page {}: {}", file_path.display(), e),
+        }
+    }
+    log::info!("Local pages loaded: {} pages from {}", pages.len(), dir.display());
+
+    *local_pages().write().unwrap() = LocalPages {
+        pages,
+        signature,
+    };
+}
+
+/// Keeps the pages in sync with the content directory. Runs forever.
+pub async fn run_watcher() {
+    let dir = content_dir();
+    loop {
+        let dir_clone = dir.clone();
+        if let Err(e) = tokio::task::spawn_blocking(move || reload_if_changed(&dir_clone)).await {
+            log::warn!("Failed to load local pages: {}", e);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,146 @@
+//! Redirects from legacy or renamed URLs, configured in `redirects`.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use regex::Regex;
+
+use crate::config::{RedirectKind, RedirectRule};
+
+
+// pattern -> compiled regex, or `None` if invalid
+static REGEX_CACHE: OnceLock<Mutex<HashMap<String, Option<Regex>>>> = OnceLock::new();
+
+fn compiled_regex(pattern: &str) -> Option<Regex> {
+    let cache = REGEX_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().unwrap();
+    cache.entry(pattern.to_string()).or_insert_with(|| {
+        Regex::new(pattern)
+            .map_err(|e| log::warn!("Invalid redirect regex {:?}: {}", pattern, e))
+            .ok()
+    }).clone()
+}
+
+/// The rest of the path after the prefix, if the prefix ends at a segment boundary.
+fn strip_prefix_segments<'p>(path: &'p str, prefix: &str) -> Option<&'p str> {
+    let rest = path.strip_prefix(prefix)?;
+    (prefix.ends_with('/') || rest.is_empty() || rest.starts_with('/')).then_some(rest)
+}
+
+/// Whether the target of a prefix rule is itself under the prefix, redirecting again forever.
+fn is_prefix_loop(rule: &RedirectRule) -> bool {
+    rule.kind == RedirectKind::Prefix && strip_prefix_segments(&rule.to, &rule.from).is_some()
+}
+
+/// Why a rule can never match or would loop, if so.
+pub fn validate(rule: &RedirectRule) -> Result<(), String> {
+    if rule.status != 301 && rule.status != 302 {
+        return Err(format!("Unsupported status {} (301 or 302)", rule.status));
+    }
+    if rule.from.is_empty() {
+        return Err("Empty from".to_string());
+    }
+    if rule.kind == RedirectKind::Regex {
+        Regex::new(&rule.from).map_err(|e| e.to_string())?;
+    }
+    if is_prefix_loop(rule) {
+        return Err(format!("Target {:?} is under the prefix, which would loop", rule.to));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct RedirectMatch<'a> {
+    pub index: usize, // in the rules
+    pub rule: &'a RedirectRule,
+    pub location: String,
+}
+
+fn location(rule: &RedirectRule, path: &str) -> Option<String> {
+    match rule.kind {
+        RedirectKind::Exact => (path == rule.from).then(|| rule.to.clone()),
+        RedirectKind::Prefix => strip_prefix_segments(path, &rule.from).map(|rest| format!("{}{}", rule.to, rest)),
+        RedirectKind::Regex => {
+            let captures = compiled_regex(&rule.from)?.captures(path)?;
+            let mut location = String::new();
+            captures.expand(&rule.to, &mut location);
+            Some(location)
+        },
+    }
+}
+
+/// The first valid rule matching the path. The query is kept unless the target has its own.
+pub fn find_redirect<'a>(rules: &'a [RedirectRule], path: &str, query: Option<&str>) -> Option<RedirectMatch<'a>> {
+    rules.iter().enumerate()
+        .filter(|(_, rule)| (rule.status == 301 || rule.status == 302) && !is_prefix_loop(rule))
+        .find_map(|(index, rule)| {
+            let mut location = location(rule, path)?;
+            if location == path {
+                // would loop
+                return None;
+            }
+            if let Some(query) = query.filter(|query| !query.is_empty() && !location.contains('?')) {
+                location = format!("{}?{}", location, query);
+            }
+            Some(RedirectMatch {
+                index,
+                rule,
+                location,
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(kind: RedirectKind, from: &str, to: &str) -> RedirectRule {
+        RedirectRule {
+            kind,
+            from: from.to_string(),
+            to: to.to_string(),
+            status: 301,
+        }
+    }
+
+    fn redirect(rules: &[RedirectRule], path: &str, query: Option<&str>) -> Option<String> {
+        find_redirect(rules, path, query).map(|found| found.location)
+    }
+
+    #[test]
+    fn exact_matches_whole_path_only() {
+        let rules = [rule(RedirectKind::Exact, "/about/", "/meta/about/")];
+        assert_eq!(redirect(&rules, "/about/", None).as_deref(), Some("/meta/about/"));
+        assert_eq!(redirect(&rules, "/about/x", None), None);
+        assert_eq!(redirect(&rules, "/about/", Some("a=1")).as_deref(), Some("/meta/about/?a=1"));
+    }
+
+    #[test]
+    fn prefix_matches_at_segment_boundary() {
+        let rules = [rule(RedirectKind::Prefix, "/old", "/new")];
+        assert_eq!(redirect(&rules, "/old", None).as_deref(), Some("/new"));
+        assert_eq!(redirect(&rules, "/old/a/", None).as_deref(), Some("/new/a/"));
+        assert_eq!(redirect(&rules, "/older/", None), None);
+
+        let rules = [rule(RedirectKind::Prefix, "/blog/", "/c/blog/")];
+        assert_eq!(redirect(&rules, "/blog/post/", None).as_deref(), Some("/c/blog/post/"));
+    }
+
+    #[test]
+    fn regex_expands_captures() {
+        let rules = [rule(RedirectKind::Regex, r"^/posts/(?P<uuid>[0-9a-f-]+)/$", "/c/blog/${uuid}/")];
+        assert_eq!(redirect(&rules, "/posts/abc-123/", None).as_deref(), Some("/c/blog/abc-123/"));
+        assert_eq!(redirect(&rules, "/posts/", None), None);
+    }
+
+    #[test]
+    fn prefix_under_itself_is_rejected() {
+        let looping = rule(RedirectKind::Prefix, "/docs", "/docs/v2");
+        assert!(validate(&looping).is_err());
+        assert_eq!(redirect(&[looping], "/docs/a", None), None);
+
+        let sibling = rule(RedirectKind::Prefix, "/docs", "/docs-v2");
+        assert!(validate(&sibling).is_ok());
+        assert_eq!(redirect(&[sibling], "/docs/a", None).as_deref(), Some("/docs-v2/a"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Paths handled by the routes of the server, which the fallback never sees.
+//! Kept in sync with the router in `alarkhabil-frontend-server`, as checked by a test.
+
+
+/// Route patterns in axum syntax: `:name` matches one segment, `*name` the rest of the path, if any.
+pub const ROUTES: [&str; 46] = [
+    "/",
+    "/meta/",
+    "/meta/:page_name/",
+    "/c/",
+    "/c/:channel_handle/",
+    "/c/:channel_handle/:post_uuid/",
+    "/c/:channel_handle/:post_uuid/revisions/",
+    "/c/:channel_handle/:post_uuid/r/:revision_uuid/",
+    "/c/:channel_handle/:post_uuid/diff/",
+    "/c/:channel_handle/:post_uuid/index.md",
+    "/c/:channel_handle/:post_uuid/index.txt",
+    "/c/:channel_handle/:post_uuid/cite.bib",
+    "/c/:channel_handle/:post_uuid/cite.ris",
+    "/c/:channel_handle/:post_uuid/cite.json",
+    "/archive/",
+    "/archive/:year/",
+    "/archive/:year/:month/",
+    "/c/:channel_handle/archive/",
+    "/c/:channel_handle/archive/:year/",
+    "/c/:channel_handle/archive/:year/:month/",
+    "/c/:channel_handle/stats/",
+    "/authors/:author_uuid/stats/",
+    "/c/:channel_handle/export.epub",
+    "/authors/:author_uuid/export.epub",
+    "/series/:series_name/",
+    "/lang/:lang_code/",
+    "/authors/",
+    "/authors/:author_uuid/",
+    "/tags/",
+    "/tags/:tag_names/",
+    "/tags/:tag_names/*excluded_tags",
+    "/search/",
+    "/opensearch.xml",
+    "/invites/",
+    "/signup/",
+    "/signin/",
+    "/account/",
+    "/branding",
+    "/branding/*path",
+    "/assets",
+    "/assets/*path",
+    "/frontend/api/v1/markdown/parse",
+    "/frontend/api/v1/config/get",
+    "/frontend/api/v1/timestamp/format",
+    "/frontend/api/v1/views/record",
+    "/frontend/api/v1/views/get",
+];
+
+fn matches(pattern: &str, path: &str) -> bool {
+    let mut segments = path.split('/');
+    for pattern_segment in pattern.split('/') {
+        if pattern_segment.starts_with('*') {
+            return segments.next().is_some();
+        }
+        match segments.next() {
+            Some(segment) if pattern_segment.starts_with(':') => {
+                if segment.is_empty() {
+                    return false;
+                }
+            },
+            Some(segment) if segment == pattern_segment => {},
+            _ => return false,
+        }
+    }
+    segments.next().is_none()
+}
+
+/// Whether a route of the server handles the path, so that the fallback never sees it.
+pub fn is_routed(path: &str) -> bool {
+    ROUTES.iter().any(|pattern| matches(pattern, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_match_one_segment() {
+        assert!(is_routed("/c/blog/"));
+        assert!(is_routed("/c/blog/archive/2024/"));
+        assert!(!is_routed("/c/blog/a/b/c/"));
+        assert!(!is_routed("/about/"));
+        assert!(!is_routed("/c//"));
+    }
+
+    #[test]
+    fn routes_are_those_of_the_server() {
+        let source = include_str!("bin/alarkhabil-frontend-server.rs");
+        let mut registered: Vec<String> = Vec::new();
+        for (call, is_nested) in [(".route(\"", false), (".nest_service(\"", true)] {
+            for rest in source.split(call).skip(1) {
+                let pattern = &rest[..rest.find('"').unwrap()];
+                registered.push(pattern.to_string());
+                if is_nested {
+                    registered.push(format!("{}/*path", pattern));
+                }
+            }
+        }
+        registered.sort();
+        let mut routes: Vec<String> = ROUTES.iter().map(|route| route.to_string()).collect();
+        routes.sort();
+        assert_eq!(routes, registered);
+    }
+
+    #[test]
+    fn wildcards_match_the_rest() {
+        assert!(is_routed("/tags/a/b/c/"));
+        assert!(is_routed("/assets/css/main.css"));
+        assert!(!is_routed("/assets-old/"));
+    }
+}
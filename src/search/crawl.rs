@@ -0,0 +1,157 @@
+//! Keeps the search index in sync with the backend.
+
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+
+use crate::config::{self, Config};
+use crate::backend_api::BackendApi;
+use crate::markdown;
+use crate::handler::{
+    PostSummary,
+    PostInfo,
+    ChannelSummary,
+    ChannelInfo,
+    AuthorSummary,
+    AuthorInfo,
+    MetaPage,
+    MetaPageListItem,
+};
+
+use super::{search_index, Document, DocumentKind};
+
+
+async fn get_json<T: DeserializeOwned>(backend_api: &BackendApi, path: &str, query: &[(&str, &str)]) -> Result<T, anyhow::Error> {
+    let query: HashMap<String, String> = query.iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    let bytes = backend_api.get_bytes(path, query).await?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Revision for content without a revision id of its own.
+fn content_hash<T: Hash>(content: &T) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+fn is_current(url: &str, revision: &str) -> bool {
+    search_index().read().unwrap().revision(url) == Some(revision)
+}
+
+fn upsert(document: Document) {
+    search_index().write().unwrap().upsert(document);
+}
+
+/// Fetches what changed since the last refresh and updates the index.
+/// Documents no longer in the backend are removed only after a complete crawl.
+pub async fn refresh(config: &Config) -> Result<(), anyhow::Error> {
+    let backend_api = BackendApi::new_v1(config);
+    let mut seen: HashSet<String> = HashSet::new();
+
+    let channels: Vec<ChannelSummary> = get_json(&backend_api, "channel/list", &[]).await?;
+    for channel in &channels {
+        let info: ChannelInfo = get_json(&backend_api, "channel/info", &[("uuid", &channel.uuid)]).await?;
+        let url = format!("/c/{}/", info.handle);
+        let revision = content_hash(&(&info.name, &info.lang, &info.description_text));
+        seen.insert(url.clone());
+        if !is_current(&url, &revision) {
+            upsert(Document {
+                kind: DocumentKind::Channel,
+                url,
+                revision,
+                title: info.name.clone(),
+                tags: vec![],
+                text: markdown::to_plain_text(&info.description_text),
+                lang: info.lang.clone(),
+                date: info.created_date,
+            });
+        }
+
+        let posts: Vec<PostSummary> = get_json(&backend_api, "channel/posts", &[("uuid", &channel.uuid)]).await?;
+        for post in posts {
+            let url = format!("/c/{}/{}/", info.handle, post.post_uuid);
+            seen.insert(url.clone());
+            if is_current(&url, &post.revision_uuid) {
+                continue;
+            }
+            let post: PostInfo = get_json(&backend_api, "post/info", &[("uuid", &post.post_uuid)]).await?;
+            upsert(Document {
+                kind: DocumentKind::Post,
+                url,
+                revision: post.revision_uuid.clone(),
+                title: post.title.clone(),
+                tags: post.tags.clone(),
+                text: markdown::to_plain_text(&post.revision_text),
+                lang: post.channel.lang.clone(),
+                date: post.revision_date,
+            });
+        }
+    }
+
+    let authors: Vec<AuthorSummary> = get_json(&backend_api, "author/list", &[]).await?;
+    for author in &authors {
+        let info: AuthorInfo = get_json(&backend_api, "author/info", &[("uuid", &author.uuid)]).await?;
+        let url = format!("/authors/{}/", info.uuid);
+        let revision = content_hash(&(&info.name, &info.description_text));
+        seen.insert(url.clone());
+        if !is_current(&url, &revision) {
+            upsert(Document {
+                kind: DocumentKind::Author,
+                url,
+                revision,
+                title: info.name.clone(),
+                tags: vec![],
+                text: markdown::to_plain_text(&info.description_text),
+                lang: "".to_string(),
+                date: info.created_date,
+            });
+        }
+    }
+
+    let meta_pages: Vec<MetaPageListItem> = get_json(&backend_api, "meta/list", &[]).await?;
+    for meta_page in &meta_pages {
+        let url = config.meta_page_url(&meta_page.page_name);
+        let revision = meta_page.updated_date.to_string();
+        seen.insert(url.clone());
+        if is_current(&url, &revision) {
+            continue;
+        }
+        let info: MetaPage = get_json(&backend_api, "meta/info", &[("page_name", &meta_page.page_name)]).await?;
+        upsert(Document {
+            kind: DocumentKind::MetaPage,
+            url,
+            revision,
+            title: info.title.clone(),
+            tags: vec![],
+            text: markdown::to_plain_text(&info.text),
+            lang: "".to_string(),
+            date: info.updated_date,
+        });
+    }
+
+    let mut index = search_index().write().unwrap();
+    for url in index.urls() {
+        if !seen.contains(&url) {
+            index.remove(&url);
+        }
+    }
+    log::info!("Search index refreshed: {} documents", index.len());
+
+    Ok(())
+}
+
+/// Refreshes the search index periodically. Runs forever.
+pub async fn run_indexer() {
+    loop {
+        let config = config::load_config().await;
+        if let Err(e) = refresh(&config).await {
+            log::warn!("Failed to refresh search index: {}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(config.search_refresh_interval.max(10))).await;
+    }
+}
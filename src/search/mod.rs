@@ -0,0 +1,320 @@
+//! In-process full-text search over the content of the backend.
+
+mod tokenize;
+mod crawl;
+
+pub use tokenize::{tokenize, is_cjk, is_arabic_ignorable, Token};
+pub use crawl::{refresh, run_indexer};
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::{OnceLock, RwLock};
+
+
+const TITLE_WEIGHT: f32 = 3.0;
+const TAG_WEIGHT: f32 = 2.0;
+const TEXT_WEIGHT: f32 = 1.0;
+
+// BM25 parameters
+const BM25_K1: f32 = 1.2;
+const BM25_B: f32 = 0.75;
+
+/// Approximate length of snippets in characters.
+const SNIPPET_CHARS: usize = 160;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DocumentKind {
+    Post,
+    Channel,
+    Author,
+    MetaPage,
+}
+
+impl DocumentKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DocumentKind::Post => "Post",
+            DocumentKind::Channel => "Channel",
+            DocumentKind::Author => "Author",
+            DocumentKind::MetaPage => "Page",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub kind: DocumentKind,
+    pub url: String, // relative url, unique within the index
+    pub revision: String, // changes whenever the content changes
+    pub title: String,
+    pub tags: Vec<String>,
+    pub text: String, // plain text
+    pub lang: String,
+    pub date: u64,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedDocument {
+    document: Document,
+    terms: Vec<String>, // distinct terms, for removal
+    length: f32, // weighted number of tokens
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub document: Document,
+    pub score: f32,
+    pub snippet_html: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchResults {
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
+}
+
+/// Inverted index with BM25 ranking.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    documents: HashMap<u32, IndexedDocument>,
+    ids: HashMap<String, u32>, // url -> id
+    postings: HashMap<String, HashMap<u32, f32>>, // term -> id -> weighted term frequency
+    next_id: u32,
+    total_length: f32,
+}
+
+impl SearchIndex {
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn revision(&self, url: &str) -> Option<&str> {
+        let id = self.ids.get(url)?;
+        self.documents.get(id).map(|indexed| indexed.document.revision.as_str())
+    }
+
+    pub fn urls(&self) -> Vec<String> {
+        self.ids.keys().cloned().collect()
+    }
+
+    /// Whether a post with the uuid is indexed, under any channel.
+    pub fn has_post(&self, post_uuid: &str) -> bool {
+        let suffix = format!("/{}/", post_uuid);
+        self.documents.values().any(|indexed| {
+            indexed.document.kind == DocumentKind::Post && indexed.document.url.ends_with(&suffix)
+        })
+    }
+
+    /// Tags of the indexed document, if its revision matches.
+    pub fn tags(&self, url: &str, revision: &str) -> Option<Vec<String>> {
+        let id = self.ids.get(url)?;
+        let document = &self.documents.get(id)?.document;
+        if document.revision == revision {
+            Some(document.tags.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Date of the latest indexed post with each tag.
+    pub fn tag_activity(&self) -> HashMap<String, u64> {
+        let mut activity: HashMap<String, u64> = HashMap::new();
+        for indexed in self.documents.values() {
+            let document = &indexed.document;
+            if document.kind != DocumentKind::Post {
+                continue;
+            }
+            for tag in &document.tags {
+                let date = activity.entry(tag.clone()).or_default();
+                *date = (*date).max(document.date);
+            }
+        }
+        activity
+    }
+
+    /// Adds the document, replacing one with the same url.
+    pub fn upsert(&mut self, document: Document) {
+        self.remove(&document.url);
+
+        let mut frequencies: HashMap<String, f32> = HashMap::new();
+        let fields = [
+            (document.title.as_str(), TITLE_WEIGHT),
+            (document.text.as_str(), TEXT_WEIGHT),
+        ];
+        let tags = document.tags.iter().map(|tag| (tag.as_str(), TAG_WEIGHT));
+        for (text, weight) in fields.into_iter().chain(tags) {
+            for token in tokenize(text) {
+                *frequencies.entry(token.term).or_default() += weight;
+            }
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let length = frequencies.values().sum();
+        for (term, frequency) in &frequencies {
+            self.postings.entry(term.clone()).or_default().insert(id, *frequency);
+        }
+        self.total_length += length;
+        self.ids.insert(document.url.clone(), id);
+        self.documents.insert(id, IndexedDocument {
+            document,
+            terms: frequencies.into_keys().collect(),
+            length,
+        });
+    }
+
+    pub fn remove(&mut self, url: &str) {
+        let id = if let Some(id) = self.ids.remove(url) {
+            id
+        } else {
+            return;
+        };
+        let indexed = if let Some(indexed) = self.documents.remove(&id) {
+            indexed
+        } else {
+            return;
+        };
+        for term in &indexed.terms {
+            if let Some(posting) = self.postings.get_mut(term) {
+                posting.remove(&id);
+                if posting.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_length -= indexed.length;
+    }
+
+    /// Documents containing every term of the query, best first.
+    pub fn search(&self, query: &str, offset: usize, limit: usize) -> SearchResults {
+        let terms: HashSet<String> = tokenize(query).into_iter().map(|token| token.term).collect();
+        if terms.is_empty() || self.documents.is_empty() {
+            return SearchResults::default();
+        }
+
+        let mut postings = Vec::new();
+        for term in &terms {
+            match self.postings.get(term) {
+                Some(posting) => postings.push(posting),
+                None => return SearchResults::default(),
+            }
+        }
+        postings.sort_by_key(|posting| posting.len());
+
+        let document_count = self.documents.len() as f32;
+        let average_length = (self.total_length / document_count).max(1.0);
+        let mut scored: Vec<(u32, f32)> = postings[0].keys()
+            .filter(|id| postings[1..].iter().all(|posting| posting.contains_key(id)))
+            .map(|&id| {
+                let length = self.documents[&id].length;
+                let score = postings.iter().map(|posting| {
+                    let frequency = posting[&id];
+                    let df = posting.len() as f32;
+                    let idf = (1.0 + (document_count - df + 0.5) / (df + 0.5)).ln();
+                    idf * frequency * (BM25_K1 + 1.0)
+                        / (frequency + BM25_K1 * (1.0 - BM25_B + BM25_B * length / average_length))
+                }).sum();
+                (id, score)
+            })
+            .collect();
+
+        scored.sort_by(|(a_id, a_score), (b_id, b_score)| {
+            b_score.total_cmp(a_score)
+                .then_with(|| self.documents[b_id].document.date.cmp(&self.documents[a_id].document.date))
+        });
+
+        let total = scored.len();
+        let hits = scored.into_iter().skip(offset).take(limit).map(|(id, score)| {
+            let document = &self.documents[&id].document;
+            let text = if document.text.is_empty() { &document.title } else { &document.text };
+            SearchHit {
+                document: document.clone(),
+                score,
+                snippet_html: snippet_html(text, &terms),
+            }
+        }).collect();
+
+        SearchResults {
+            total,
+            hits,
+        }
+    }
+}
+
+static SEARCH_INDEX: OnceLock<RwLock<SearchIndex>> = OnceLock::new();
+
+pub fn search_index() -> &'static RwLock<SearchIndex> {
+    SEARCH_INDEX.get_or_init(|| RwLock::new(SearchIndex::default()))
+}
+
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn char_boundary_before(text: &str, mut index: usize) -> usize {
+    index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Escaped excerpt around the first match, with matches wrapped in `<mark>`.
+fn snippet_html(text: &str, terms: &HashSet<String>) -> String {
+    let text = text.trim();
+    let mut matches: Vec<Range<usize>> = Vec::new();
+    for token in tokenize(text) {
+        if !terms.contains(&token.term) {
+            continue;
+        }
+        match matches.last_mut() {
+            Some(last) if token.range.start <= last.end => last.end = last.end.max(token.range.end),
+            _ => matches.push(token.range),
+        }
+    }
+
+    // about a third of the snippet before the first match
+    let first = matches.first().map(|range| range.start).unwrap_or(0);
+    let lead = text[..first].chars().rev().take(SNIPPET_CHARS / 3).map(|c| c.len_utf8()).sum::<usize>();
+    let start = first - lead;
+    let length = text[start..].chars().take(SNIPPET_CHARS).map(|c| c.len_utf8()).sum::<usize>();
+    let end = start + length;
+
+    let mut html = String::new();
+    if start > 0 {
+        html.push('…');
+    }
+    let mut position = start;
+    for range in matches.iter().filter(|range| range.end > start && range.start < end) {
+        let match_start = range.start.max(start);
+        let match_end = char_boundary_before(text, range.end.min(end));
+        html.push_str(&escape_html(&text[position..match_start]));
+        html.push_str("<mark>");
+        html.push_str(&escape_html(&text[match_start..match_end]));
+        html.push_str("</mark>");
+        position = match_end;
+    }
+    html.push_str(&escape_html(&text[position..end]));
+    if end < text.len() {
+        html.push('…');
+    }
+    html.replace('\n', " ")
+}
@@ -0,0 +1,162 @@
+//! Tokenization for the search index.
+//!
+//! Runs of letters and digits become single tokens. CJK text, which has no
+//! spaces between words, is split into single characters and overlapping
+//! bigrams instead, so that one-character queries match inside longer runs.
+
+use std::ops::Range;
+
+
+/// A normalized term with its byte range in the original text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub term: String,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Word,
+    Cjk,
+    Other,
+}
+
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF // Hiragana, Katakana
+        | 0x3400..=0x4DBF // CJK Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF // Hangul syllables
+        | 0xF900..=0xFAFF // CJK compatibility ideographs
+        | 0xFF66..=0xFF9F // halfwidth Katakana
+        | 0x20000..=0x3134F // CJK Extensions B-G
+    )
+}
+
+/// Arabic harakat, Quranic marks and tatweel, which are ignored.
+pub fn is_arabic_ignorable(c: char) -> bool {
+    matches!(c as u32,
+        0x0610..=0x061A
+        | 0x0640
+        | 0x064B..=0x065F
+        | 0x0670
+        | 0x06D6..=0x06ED
+    )
+}
+
+/// Normalizes one character: case, fullwidth forms and Arabic letter variants.
+/// Returns `None` for characters to be ignored.
+fn normalize_char(c: char) -> Option<char> {
+    if is_arabic_ignorable(c) {
+        return None;
+    }
+    let c = match c {
+        'أ' | 'إ' | 'آ' | 'ٱ' => 'ا',
+        'ى' => 'ي',
+        'ة' => 'ه',
+        'ؤ' => 'و',
+        'ئ' => 'ي',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c), // fullwidth ASCII
+        _ => c,
+    };
+    Some(c.to_lowercase().next().unwrap_or(c))
+}
+
+fn classify(c: char) -> CharClass {
+    if is_cjk(c) {
+        CharClass::Cjk
+    } else if c.is_alphanumeric() {
+        CharClass::Word
+    } else {
+        CharClass::Other
+    }
+}
+
+/// Splits the text into normalized tokens.
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+
+    // (normalized char, byte range in text) of the current run
+    let mut run: Vec<(char, Range<usize>)> = Vec::new();
+    let mut run_class = CharClass::Other;
+
+    let flush = |run: &mut Vec<(char, Range<usize>)>, class: CharClass, tokens: &mut Vec<Token>| {
+        match class {
+            CharClass::Word if !run.is_empty() => {
+                tokens.push(Token {
+                    term: run.iter().map(|(c, _)| *c).collect(),
+                    range: run[0].1.start..run[run.len() - 1].1.end,
+                });
+            },
+            CharClass::Cjk => {
+                for (i, (c, range)) in run.iter().enumerate() {
+                    tokens.push(Token {
+                        term: c.to_string(),
+                        range: range.clone(),
+                    });
+                    if let Some((next, next_range)) = run.get(i + 1) {
+                        tokens.push(Token {
+                            term: [*c, *next].iter().collect(),
+                            range: range.start..next_range.end,
+                        });
+                    }
+                }
+            },
+            _ => {},
+        }
+        run.clear();
+    };
+
+    for (offset, c) in text.char_indices() {
+        let range = offset..offset + c.len_utf8();
+        let normalized = if let Some(normalized) = normalize_char(c) {
+            normalized
+        } else {
+            // ignorable marks extend the current run
+            if let Some(last) = run.last_mut() {
+                last.1.end = range.end;
+            }
+            continue;
+        };
+
+        let class = classify(normalized);
+        if class != run_class {
+            flush(&mut run, run_class, &mut tokens);
+            run_class = class;
+        }
+        if class != CharClass::Other {
+            run.push((normalized, range));
+        }
+    }
+    flush(&mut run, run_class, &mut tokens);
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(text: &str) -> Vec<String> {
+        tokenize(text).into_iter().map(|token| token.term).collect()
+    }
+
+    #[test]
+    fn cjk_runs_have_unigrams_and_bigrams() {
+        assert_eq!(terms("東京都"), vec!["東", "東京", "京", "京都", "都"]);
+    }
+
+    #[test]
+    fn single_cjk_query_matches_inside_run() {
+        let text_terms = terms("東京都");
+        for term in terms("東") {
+            assert!(text_terms.contains(&term), "{} is not indexed", term);
+        }
+    }
+
+    #[test]
+    fn words_are_normalized() {
+        assert_eq!(terms("Hello, ＷＯＲＬＤ"), vec!["hello", "world"]);
+        assert_eq!(terms("مكتبة"), vec!["مكتبه"]);
+    }
+}
@@ -4,10 +4,27 @@ use url::Url;
 
 use crate::template::{
     NavigationItem,
+    NavigationLink,
+    AlternateLink,
     ContentTemplateItem,
     content_templates,
 };
-use crate::config::Config;
+use crate::config::{Config, Announcement};
+use crate::unix_time::UnixTime;
+use crate::pages::{local_pages, PageNavigation};
+use crate::lang::TextDirection;
+use crate::navigation;
+
+/// Configured items followed by local pages not already linked.
+fn navigation_items(configured: &[NavigationItem], position: PageNavigation) -> Vec<NavigationItem> {
+    let mut items = configured.to_vec();
+    for item in local_pages().read().unwrap().navigation_items(position) {
+        if !items.iter().any(|other| other.url == item.url) {
+            items.push(item);
+        }
+    }
+    items
+}
 
 #[derive(Template)]
 #[template(path = "base.html")]
@@ -16,11 +33,18 @@ pub struct BaseTemplate {
     pub title: String, // title on <h1> tag, if any
     pub page_title: String, // full title on <title> tag
     pub site_name: String,
+    pub lang: String, // on <html lang="...">
+    pub dir: String, // on <html dir="...">
     pub site_description: String,
     pub site_copyright: String,
-    pub header_navigation: Vec<NavigationItem>,
-    pub footer_navigation: Vec<NavigationItem>,
+    pub header_navigation: Vec<NavigationLink>,
+    pub footer_navigation: Vec<NavigationLink>,
     pub og_image: String, // absolute url
+    pub announcements: Vec<Announcement>, // active on this page
+    pub alternate_links: Vec<AlternateLink>,
+    pub canonical_url: String, // absolute url for <link rel="canonical">, or empty
+    pub prev_url: String, // absolute url for <link rel="prev">, or empty
+    pub next_url: String, // absolute url for <link rel="next">, or empty
     pub content_html: String,
     pub content_templates: Vec<ContentTemplateItem>,
     pub site_config_json: String,
@@ -43,6 +67,7 @@ impl BaseTemplate {
             title.to_string()
         };
 
+        let path = url;
         let url = top_url.join(url)?.to_string();
         let og_image = top_url.join(&config.og_image)?.to_string();
 
@@ -51,14 +76,55 @@ impl BaseTemplate {
             title,
             page_title,
             site_name: config.site_name.clone(),
+            lang: config.site_lang.clone(),
+            dir: TextDirection::detect(&config.site_lang, &config.site_name).to_string(),
             site_description: config.site_description.clone(),
             site_copyright: config.site_copyright.clone(),
-            header_navigation: config.header_navigation.clone(),
-            footer_navigation: config.footer_navigation.clone(),
+            header_navigation: navigation::links(&navigation_items(&config.header_navigation, PageNavigation::Header), path, &top_url),
+            footer_navigation: navigation::links(&navigation_items(&config.footer_navigation, PageNavigation::Footer), path, &top_url),
             og_image,
+            announcements: config.active_announcements(path, UnixTime::now()),
+            alternate_links: vec![],
+            canonical_url: "".to_string(),
+            prev_url: "".to_string(),
+            next_url: "".to_string(),
             content_html: content_html.to_string(),
             content_templates: content_templates().to_owned(),
             site_config_json: serde_json::to_string(config)?,
         })
     }
+
+    /// Sets `<link rel="prev">` and `<link rel="next">`. Empty urls are omitted.
+    pub fn with_sequence_links(self, prev_url: &str, next_url: &str) -> Result<Self, anyhow::Error> {
+        let url = Url::parse(&self.url)?;
+        let absolute = |relative: &str| -> Result<String, anyhow::Error> {
+            Ok(if relative.is_empty() {
+                "".to_string()
+            } else {
+                url.join(relative)?.to_string()
+            })
+        };
+        Ok(Self {
+            prev_url: absolute(prev_url)?,
+            next_url: absolute(next_url)?,
+            ..self
+        })
+    }
+
+    /// Sets `<link rel="canonical">`, also used as the OGP url.
+    pub fn with_canonical_url(self, canonical_url: &str) -> Result<Self, anyhow::Error> {
+        let canonical_url = Url::parse(&self.url)?.join(canonical_url)?.to_string();
+        Ok(Self {
+            url: canonical_url.clone(),
+            canonical_url,
+            ..self
+        })
+    }
+
+    pub fn with_alternate_links(self, alternate_links: Vec<AlternateLink>) -> Self {
+        Self {
+            alternate_links,
+            ..self
+        }
+    }
 }
@@ -13,6 +13,8 @@ use axum::{
     response::{Html, IntoResponse, Response},
 };
 
+use crate::diff::DiffHunk;
+
 pub struct HtmlTemplate<T> (
     pub T
 )
@@ -33,10 +35,55 @@ where
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NavigationItem {
-    pub url: String, // relative or absolute url
-    pub text: String, // link text
+    #[serde(default)]
+    pub url: String, // relative or absolute url; empty for a submenu heading
+
+    #[serde(default)]
+    pub text: String, // link text; an item with a source and no text is replaced by the links
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<NavigationSource>, // links appended to the children
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<NavigationItem>, // submenu
+}
+
+/// Navigation links generated from backend data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NavigationSource {
+    /// Every meta page, at its vanity route if any.
+    MetaPages,
+
+    /// Channels in the order of `channel/list`.
+    Channels {
+        limit: Option<usize>,
+    },
+}
+
+/// Navigation item as rendered for the current page.
+#[derive(Debug, Clone)]
+pub struct NavigationLink {
+    pub url: String,
+    pub text: String,
+    pub current: String, // aria-current: "page", "true" for the parent of the page, or empty
+    pub is_external: bool,
+    pub submenu_html: String, // children as a nested `navigation-submenu` list, or empty
+}
+
+#[derive(Template)]
+#[template(path = "navigation_submenu.html")]
+pub struct NavigationSubmenuTemplate {
+    pub items: Vec<NavigationLink>,
+}
+
+/// `<link rel="alternate" hreflang="...">` to a version of the page in another language
+#[derive(Debug, Clone)]
+pub struct AlternateLink {
+    pub hreflang: String, // or "x-default"
+    pub url: String, // absolute url
 }
 
 /// content of `<template>` tag
@@ -80,10 +127,17 @@ pub struct ContentPostListTemplate {
     pub post_list_html: String,
 }
 
+#[derive(Template)]
+#[template(path = "content_popular_posts.html")]
+pub struct ContentPopularPostsTemplate {
+    pub post_list_html: String,
+}
+
 #[derive(Template)]
 #[template(path = "content_meta_page_list_item.html")]
 pub struct ContentMetaPageListItemTemplate {
     pub page_name: String,
+    pub page_url: String, // vanity route or /meta/:page_name/
     pub title: String,
     pub date: String,
     pub date_value: String, // for <time datetime="...">
@@ -102,11 +156,13 @@ pub struct ContentPostListItemTemplate {
     pub title: String,
     pub date: String,
     pub date_value: String, // for <time datetime="...">
+    pub date_alt: String, // in the secondary calendar, if any
     pub author_uuid: String,
     pub author_name: String,
     pub channel_handle: String,
     pub channel_name: String,
     pub channel_lang: String,
+    pub dir: String, // from channel_lang, or the title if unknown
 }
 
 #[derive(Template)]
@@ -115,6 +171,7 @@ pub struct ContentChannelListItemTemplate {
     pub channel_handle: String,
     pub channel_name: String,
     pub channel_lang: String,
+    pub dir: String, // from channel_lang, or the name if unknown
 }
 
 #[derive(Template)]
@@ -123,12 +180,22 @@ pub struct ContentChannelTemplate {
     pub channel_handle: String,
     pub channel_name: String,
     pub channel_lang: String,
+    pub dir: String, // from channel_lang, or the name if unknown
     pub channel_description_html: String,
     pub channel_date: String,
     pub channel_date_value: String, // for <time datetime="...">
+    pub contributor_list_html: String,
     pub post_list_html: String,
 }
 
+#[derive(Template)]
+#[template(path = "content_channel_contributor.html")]
+pub struct ContentChannelContributorTemplate {
+    pub author_uuid: String,
+    pub author_name: String,
+    pub post_count: usize, // in the channel
+}
+
 #[derive(Template)]
 #[template(path = "content_post.html")]
 pub struct ContentPostTemplate {
@@ -136,13 +203,24 @@ pub struct ContentPostTemplate {
     pub title: String,
     pub date: String,
     pub date_value: String, // for <time datetime="...">
+    pub date_alt: String, // in the secondary calendar, if any
     pub author_uuid: String,
     pub author_name: String,
     pub channel_handle: String,
     pub channel_name: String,
     pub channel_lang: String,
+    pub dir: String, // from channel_lang, or the title if unknown
+    pub title_dir: String, // from the first strong character of the title
     pub tag_list_html: String,
     pub content_html: String,
+    pub related_post_list_html: String, // empty if none
+    pub series_html: String, // empty if not in a series
+    pub revision_notice_html: String, // empty for the current revision
+    pub citation: String, // preformatted, or empty to hide the citation block
+    pub older_post_url: String, // empty if none
+    pub older_post_title: String,
+    pub newer_post_url: String, // empty if none
+    pub newer_post_title: String,
 }
 
 #[derive(Template)]
@@ -160,9 +238,20 @@ pub struct ContentAuthorTemplate {
     pub author_date: String,
     pub author_date_value: String, // for <time datetime="...">
     pub author_description_html: String,
+    pub channel_list_html: String,
     pub post_list_html: String,
 }
 
+#[derive(Template)]
+#[template(path = "content_author_channel.html")]
+pub struct ContentAuthorChannelTemplate {
+    pub channel_handle: String,
+    pub channel_name: String,
+    pub channel_lang: String,
+    pub dir: String,
+    pub post_count: usize, // by the author
+}
+
 #[derive(Template)]
 #[template(path = "content_tag_list_item.html")]
 pub struct ContentTagListItemTemplate {
@@ -179,22 +268,322 @@ impl ContentTagListItemTemplate {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct TagFilter {
+    pub tag_name: String,
+    pub is_excluded: bool,
+    pub remove_url: String, // the page without this filter
+}
+
 #[derive(Template)]
 #[template(path = "content_tag.html")]
 pub struct ContentTagTemplate {
+    pub tag_name: String, // all the filters for combined tags
+    pub tag_name_urlencoded: String, // included tags joined with '+'
+    pub filters: Vec<TagFilter>, // empty for a single tag
+    pub post_count: usize,
+    pub post_list_html: String,
+}
+
+#[derive(Template)]
+#[template(path = "content_tag_index_item.html")]
+pub struct ContentTagIndexItemTemplate {
     pub tag_name: String,
     pub tag_name_urlencoded: String,
+    pub page_count: u64,
+    pub weight: u64, // 1 to 5, for the tag cloud
+}
+
+#[derive(Debug, Clone)]
+pub struct TagIndexOption {
+    pub label: String,
+    pub url: String,
+    pub is_current: bool,
+}
+
+#[derive(Template)]
+#[template(path = "content_tag_index.html")]
+pub struct ContentTagIndexTemplate {
+    pub is_cloud: bool,
+    pub sort_options: Vec<TagIndexOption>,
+    pub view_options: Vec<TagIndexOption>,
+    pub tag_list_html: String,
+    pub is_empty: bool,
+    pub hidden_count: usize, // tags below min_page_count
+    pub min_page_count: u64,
+    pub show_all_url: String,
+    pub sort_notice: String, // why the order may be incomplete; empty if none
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveMonth {
+    pub label: String, // YYYY-MM
+    pub url: String,
+    pub count: usize,
+    pub is_current: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveYear {
+    pub label: String, // YYYY
+    pub url: String,
+    pub count: usize,
+    pub is_current: bool,
+    pub months: Vec<ArchiveMonth>,
+}
+
+#[derive(Template)]
+#[template(path = "content_archive.html")]
+pub struct ContentArchiveTemplate {
+    pub archive_title: String,
+    pub archive_url: String, // /archive/ or /c/:channel_handle/archive/
+    pub channel_handle: String, // empty for the whole site
+    pub channel_name: String,
+    pub archive_years: Vec<ArchiveYear>,
     pub post_list_html: String,
 }
 
-impl ContentTagTemplate {
-    pub fn new(tag_name: &str, post_list_html: &str) -> Self {
-        Self {
-            tag_name: tag_name.to_string(),
-            tag_name_urlencoded: urlencoding::encode(tag_name).to_string(),
-            post_list_html: post_list_html.to_string(),
-        }
-    }
+#[derive(Template)]
+#[template(path = "content_search_result_item.html")]
+pub struct ContentSearchResultItemTemplate {
+    pub kind: String,
+    pub url: String,
+    pub title: String,
+    pub lang: String,
+    pub dir: String,
+    pub date: String, // empty if not applicable
+    pub date_value: String,
+    pub snippet_html: String,
+}
+
+#[derive(Template)]
+#[template(path = "content_search.html")]
+pub struct ContentSearchTemplate {
+    pub query: String,
+    pub result_count: usize,
+    pub result_list_html: String,
+    pub prev_url: String, // empty if none
+    pub next_url: String, // empty if none
+}
+
+#[derive(Template)]
+#[template(path = "opensearch.xml")]
+pub struct OpenSearchDescriptionTemplate {
+    pub short_name: String,
+    pub description: String,
+    pub search_url: String,
+    pub icon_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct LangNavItem {
+    pub label: String,
+    pub lang: String, // empty for all languages
+    pub url: String,
+    pub is_current: bool,
+}
+
+#[derive(Template)]
+#[template(path = "content_lang_nav.html")]
+pub struct ContentLangNavTemplate {
+    pub items: Vec<LangNavItem>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SeriesNavPart {
+    pub number: usize, // from 1
+    pub url: String,
+    pub title: String,
+    pub is_current: bool,
+}
+
+#[derive(Template)]
+#[template(path = "content_series_nav.html")]
+pub struct ContentSeriesNavTemplate {
+    pub series_title: String,
+    pub series_url: String,
+    pub position: usize, // from 1
+    pub part_count: usize,
+    pub parts: Vec<SeriesNavPart>,
+}
+
+#[derive(Template)]
+#[template(path = "content_series.html")]
+pub struct ContentSeriesTemplate {
+    pub series_title: String,
+    pub part_count: usize,
+    pub part_html_list: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsTag {
+    pub tag_name: String,
+    pub tag_name_urlencoded: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsYear {
+    pub label: String,
+    pub url: String,
+    pub is_current: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeatmapCell {
+    pub x: u32,
+    pub y: u32,
+    pub size: u32,
+    pub fill: String, // presentation attribute, as inline styles are blocked by the CSP
+    pub level: usize, // 0 for no posts
+    pub title: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeatmapLabel {
+    pub x: u32,
+    pub y: u32,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Heatmap {
+    pub width: u32,
+    pub height: u32,
+    pub label: String,
+    pub cells: Vec<HeatmapCell>,
+    pub month_labels: Vec<HeatmapLabel>,
+    pub weekday_labels: Vec<HeatmapLabel>,
+}
+
+#[derive(Template)]
+#[template(path = "content_stats.html")]
+pub struct ContentStatsTemplate {
+    pub subject_title: String, // channel or author name
+    pub subject_url: String,
+    pub stats_url: String,
+    pub post_count: usize,
+    pub first_date: String, // empty if no post
+    pub first_date_value: String,
+    pub latest_date: String,
+    pub latest_date_value: String,
+    pub tags: Vec<StatsTag>,
+    pub max_tag_count: usize,
+    pub tags_complete: bool, // false if some posts were not counted
+    pub years: Vec<StatsYear>,
+    pub is_last_year: bool,
+    pub heatmap: Heatmap,
+}
+
+#[derive(Debug, Clone)]
+pub struct PostRevisionItem {
+    pub revision_uuid: String,
+    pub url: String,
+    pub title: String,
+    pub date: String,
+    pub date_value: String, // for <time datetime="...">
+    pub is_current: bool,
+    pub diff_url: String, // changes from the previous revision, or empty for the first
+}
+
+#[derive(Template)]
+#[template(path = "content_post_revisions.html")]
+pub struct ContentPostRevisionsTemplate {
+    pub title: String,
+    pub post_url: String,
+    pub channel_lang: String,
+    pub dir: String,
+    pub revisions: Vec<PostRevisionItem>, // newest first
+    pub diff_url: String, // for the comparison form
+}
+
+#[derive(Template)]
+#[template(path = "content_post_revision_notice.html")]
+pub struct ContentPostRevisionNoticeTemplate {
+    pub date: String, // of the revision shown
+    pub date_value: String,
+    pub post_url: String,
+    pub revisions_url: String,
+}
+
+#[derive(Template)]
+#[template(path = "content_post_diff.html")]
+pub struct ContentPostDiffTemplate {
+    pub title_html: String, // with changed words in <del> or <ins>
+    pub post_url: String,
+    pub revisions_url: String,
+    pub channel_lang: String,
+    pub dir: String,
+    pub from_url: String,
+    pub from_date: String,
+    pub from_date_value: String,
+    pub to_url: String,
+    pub to_date: String,
+    pub to_date_value: String,
+    pub is_identical: bool,
+    pub rendered_html: String,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EpubChapterItem {
+    pub id: String,
+    pub file_name: String,
+    pub title: String,
+    pub has_remote_resources: bool,
+}
+
+#[derive(Template)]
+#[template(path = "epub_package.opf", escape = "html")]
+pub struct EpubPackageTemplate {
+    pub identifier: String,
+    pub title: String,
+    pub creators: Vec<String>,
+    pub publisher: String,
+    pub description: String,
+    pub lang: String,
+    pub dir: String, // empty if unknown
+    pub url: String,
+    pub date: String, // YYYY-MM-DD
+    pub modified: String, // YYYY-MM-DDThh:mm:ssZ
+    pub is_rtl: bool,
+    pub chapters: Vec<EpubChapterItem>,
+}
+
+#[derive(Template)]
+#[template(path = "epub_nav.xhtml", escape = "html")]
+pub struct EpubNavTemplate {
+    pub title: String,
+    pub lang: String,
+    pub dir: String, // empty if unknown
+    pub chapters: Vec<EpubChapterItem>,
+}
+
+#[derive(Template)]
+#[template(path = "epub_title.xhtml", escape = "html")]
+pub struct EpubTitlePageTemplate {
+    pub title: String,
+    pub creators: String,
+    pub publisher: String,
+    pub lang: String,
+    pub dir: String, // empty if unknown
+    pub url: String,
+    pub description_html: String, // XHTML
+    pub notice: String, // or empty
+}
+
+#[derive(Template)]
+#[template(path = "epub_chapter.xhtml", escape = "html")]
+pub struct EpubChapterTemplate {
+    pub title: String,
+    pub title_dir: String, // empty if unknown
+    pub byline: String,
+    pub lang: String,
+    pub dir: String, // empty if unknown
+    pub date: String,
+    pub date_value: String,
+    pub url: String,
+    pub content_html: String, // XHTML
 }
 
 // for use by JavaScript rendering
@@ -223,6 +612,7 @@ pub fn content_templates() -> &'static Vec<ContentTemplateItem> {
                 "content-meta-page-list-item".to_string(),
                 ContentMetaPageListItemTemplate::render(&ContentMetaPageListItemTemplate {
                     page_name: "".to_string(),
+                    page_url: "".to_string(),
                     title: "".to_string(),
                     date: "".to_string(),
                     date_value: "".to_string(),
@@ -241,11 +631,13 @@ pub fn content_templates() -> &'static Vec<ContentTemplateItem> {
                     title: "".to_string(),
                     date: "".to_string(),
                     date_value: "".to_string(),
+                    date_alt: "".to_string(),
                     author_uuid: "".to_string(),
                     author_name: "".to_string(),
                     channel_handle: "".to_string(),
                     channel_name: "".to_string(),
                     channel_lang: "".to_string(),
+                    dir: "".to_string(),
                 }).unwrap(),
             ),
             (
@@ -254,6 +646,7 @@ pub fn content_templates() -> &'static Vec<ContentTemplateItem> {
                     channel_handle: "".to_string(),
                     channel_name: "".to_string(),
                     channel_lang: "".to_string(),
+                    dir: "".to_string(),
                 }).unwrap(),
             ),
             (
@@ -262,9 +655,11 @@ pub fn content_templates() -> &'static Vec<ContentTemplateItem> {
                     channel_handle: "".to_string(),
                     channel_name: "".to_string(),
                     channel_lang: "".to_string(),
+                    dir: "".to_string(),
                     channel_description_html: "".to_string(),
                     channel_date: "".to_string(),
                     channel_date_value: "".to_string(),
+                    contributor_list_html: "".to_string(),
                     post_list_html: "".to_string(),
                 }).unwrap(),
             ),
@@ -275,13 +670,24 @@ pub fn content_templates() -> &'static Vec<ContentTemplateItem> {
                     title: "".to_string(),
                     date: "".to_string(),
                     date_value: "".to_string(),
+                    date_alt: "".to_string(),
                     author_uuid: "".to_string(),
                     author_name: "".to_string(),
                     channel_handle: "".to_string(),
                     channel_name: "".to_string(),
                     channel_lang: "".to_string(),
+                    dir: "".to_string(),
+                    title_dir: "".to_string(),
                     tag_list_html: "".to_string(),
                     content_html: "".to_string(),
+                    related_post_list_html: "".to_string(),
+                    series_html: "".to_string(),
+                    revision_notice_html: "".to_string(),
+                    citation: "".to_string(),
+                    older_post_url: "".to_string(),
+                    older_post_title: "".to_string(),
+                    newer_post_url: "".to_string(),
+                    newer_post_title: "".to_string(),
                 }).unwrap(),
             ),
             (
@@ -291,6 +697,14 @@ pub fn content_templates() -> &'static Vec<ContentTemplateItem> {
                     author_name: "".to_string(),
                 }).unwrap(),
             ),
+            (
+                "content-channel-contributor".to_string(),
+                ContentChannelContributorTemplate::render(&ContentChannelContributorTemplate {
+                    author_uuid: "".to_string(),
+                    author_name: "".to_string(),
+                    post_count: 0,
+                }).unwrap(),
+            ),
             (
                 "content-author".to_string(),
                 ContentAuthorTemplate::render(&ContentAuthorTemplate {
@@ -299,6 +713,7 @@ pub fn content_templates() -> &'static Vec<ContentTemplateItem> {
                     author_date: "".to_string(),
                     author_date_value: "".to_string(),
                     author_description_html: "".to_string(),
+                    channel_list_html: "".to_string(),
                     post_list_html: "".to_string(),
                 }).unwrap(),
             ),
@@ -314,6 +729,8 @@ pub fn content_templates() -> &'static Vec<ContentTemplateItem> {
                 ContentTagTemplate::render(&ContentTagTemplate {
                     tag_name: "".to_string(),
                     tag_name_urlencoded: "".to_string(),
+                    filters: vec![],
+                    post_count: 0,
                     post_list_html: "".to_string(),
                 }).unwrap(),
             ),
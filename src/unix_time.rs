@@ -4,10 +4,15 @@ use std::fmt::{
     Display,
     Formatter,
 };
+use std::str::FromStr;
 use std::time::SystemTime;
 
 use chrono_tz::Tz;
 use chrono::prelude::*;
+use chrono::format::{Item, StrftimeItems};
+
+use crate::lang::LangTag;
+use crate::calendar::Calendar;
 
 
 /// Elapsed non-leap seconds since UNIX epoch.
@@ -23,6 +28,13 @@ impl UnixTime {
         }
     }
 
+    /// Returns `None` if the time cannot be represented as a date.
+    pub fn checked_new(secs: u64) -> Option<Self> {
+        let secs_i64 = i64::try_from(secs).ok()?;
+        DateTime::from_timestamp(secs_i64, 0)?;
+        Some(Self::new(secs))
+    }
+
     pub fn now() -> Self {
         match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
             Ok(n) => n.as_secs(),
@@ -31,7 +43,7 @@ impl UnixTime {
     }
 
     fn naive_datetime(&self) -> NaiveDateTime {
-        NaiveDateTime::from_timestamp_opt(self.secs as i64, 0).unwrap()
+        DateTime::from_timestamp(self.secs as i64, 0).unwrap().naive_utc()
     }
 
     pub fn to_utc_datetime_string(&self) -> String {
@@ -40,14 +52,60 @@ impl UnixTime {
         utc_datetime.format("%Y-%m-%dT%H:%M:%S%z").to_string()
     }
 
+    /// Parses RFC 3339, or a date with an optional time (`YYYY-MM-DD[ HH:MM[:SS]]`) in the timezone.
+    pub fn parse_in_timezone(text: &str, timezone: Tz) -> Option<Self> {
+        let text = text.trim();
+        if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+            return u64::try_from(datetime.timestamp()).ok().map(Self::new);
+        }
+        let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"].iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+            .or_else(|| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))?;
+        let datetime = timezone.from_local_datetime(&naive).earliest()?;
+        u64::try_from(datetime.timestamp()).ok().map(Self::new)
+    }
+
     pub fn to_datetime(&self, timezone: Tz) -> DateTime<Tz> {
         let utc_datetime = self.naive_datetime();
         timezone.from_utc_datetime(&utc_datetime)
     }
 
     pub fn default_format_in_timezone(&self, timezone: Tz) -> String {
+        self.format_in_timezone(timezone, &DateTimePattern::Default, None)
+    }
+
+    /// Formats with the pattern, using localized names if a locale is given.
+    pub fn format_in_timezone(&self, timezone: Tz, pattern: &DateTimePattern, locale: Option<Locale>) -> String {
         let datetime = self.to_datetime(timezone);
-        datetime.format("%Y-%m-%d %H:%M:%S %Z").to_string()
+        match locale {
+            Some(locale) => datetime.format_localized(pattern.as_str(), locale).to_string(),
+            None => datetime.format(pattern.as_str()).to_string(),
+        }
+    }
+
+    /// Date in a non-Gregorian calendar, if supported for this time.
+    pub fn format_calendar(&self, timezone: Tz, calendar: Calendar) -> Option<String> {
+        calendar.format_date(self.to_datetime(timezone).date_naive())
+    }
+
+    /// Human readable distance from `now`, like "3 days ago" or "in 2 hours".
+    /// Supports English, Japanese and Arabic, falling back to English.
+    pub fn format_relative(&self, now: UnixTime, lang: &str) -> String {
+        let (secs, past) = if now.secs >= self.secs {
+            (now.secs - self.secs, true)
+        } else {
+            (self.secs - now.secs, false)
+        };
+
+        let (count, unit) = RelativeUnit::split(secs);
+        let primary = LangTag::parse(lang)
+            .map(|tag| tag.primary_language().to_string())
+            .unwrap_or_default();
+        match primary.as_str() {
+            "ja" => unit.format_ja(count, past),
+            "ar" => unit.format_ar(count, past),
+            _ => unit.format_en(count, past),
+        }
     }
 }
 
@@ -57,6 +115,17 @@ impl Display for UnixTime {
     }
 }
 
+impl FromStr for UnixTime {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let secs = s.trim().parse::<u64>()
+            .map_err(|_| anyhow::anyhow!("Invalid timestamp: {:?}", s))?;
+        Self::checked_new(secs)
+            .ok_or_else(|| anyhow::anyhow!("Timestamp out of range: {}", secs))
+    }
+}
+
 impl From<u64> for UnixTime {
     fn from(secs: u64) -> Self {
         Self::new(secs)
@@ -68,3 +137,162 @@ impl From<UnixTime> for u64 {
         unix_time.secs
     }
 }
+
+
+/// Named or custom (strftime) pattern for formatting dates.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DateTimePattern {
+    Default,
+    Date,
+    Time,
+    Iso8601,
+    Rfc2822,
+    LocaleDate,
+    LocaleDateTime,
+    Custom(String),
+}
+
+impl DateTimePattern {
+    pub fn as_str(&self) -> &str {
+        match self {
+            DateTimePattern::Default => "%Y-%m-%d %H:%M:%S %Z",
+            DateTimePattern::Date => "%Y-%m-%d",
+            DateTimePattern::Time => "%H:%M:%S",
+            DateTimePattern::Iso8601 => "%Y-%m-%dT%H:%M:%S%:z",
+            DateTimePattern::Rfc2822 => "%a, %d %b %Y %H:%M:%S %z",
+            DateTimePattern::LocaleDate => "%x",
+            DateTimePattern::LocaleDateTime => "%c",
+            DateTimePattern::Custom(pattern) => pattern,
+        }
+    }
+}
+
+impl FromStr for DateTimePattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = match s {
+            "default" => DateTimePattern::Default,
+            "date" => DateTimePattern::Date,
+            "time" => DateTimePattern::Time,
+            "iso8601" => DateTimePattern::Iso8601,
+            "rfc2822" => DateTimePattern::Rfc2822,
+            "locale_date" => DateTimePattern::LocaleDate,
+            "locale_datetime" => DateTimePattern::LocaleDateTime,
+            _ => {
+                if s.is_empty() || s.len() > 256 || StrftimeItems::new(s).any(|item| item == Item::Error) {
+                    return Err(anyhow::anyhow!("Invalid pattern: {:?}", s));
+                }
+                DateTimePattern::Custom(s.to_string())
+            },
+        };
+        Ok(pattern)
+    }
+}
+
+/// Resolves a BCP 47 tag like `ja` or `ar-EG` to a locale known to chrono.
+pub fn parse_locale(lang: &str) -> Option<Locale> {
+    let tag = LangTag::parse(lang)?;
+    let primary = tag.primary_language();
+    let posix = tag.to_string().replace('-', "_");
+    let region = posix.split('_')
+        .skip(1)
+        .find(|subtag| subtag.len() == 2)
+        .map(|region| format!("{}_{}", primary, region.to_ascii_uppercase()));
+    let default_region = match primary {
+        "ar" => "ar_SA".to_string(),
+        "en" => "en_US".to_string(),
+        "ja" => "ja_JP".to_string(),
+        "ko" => "ko_KR".to_string(),
+        "zh" => "zh_CN".to_string(),
+        "fa" => "fa_IR".to_string(),
+        "he" => "he_IL".to_string(),
+        "ur" => "ur_PK".to_string(),
+        _ => format!("{}_{}", primary, primary.to_ascii_uppercase()),
+    };
+
+    region.into_iter()
+        .chain([default_region])
+        .find_map(|name| Locale::try_from(name.as_str()).ok())
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RelativeUnit {
+    Now,
+    Minute,
+    Hour,
+    Day,
+    Month,
+    Year,
+}
+
+impl RelativeUnit {
+    fn split(secs: u64) -> (u64, Self) {
+        const MINUTE: u64 = 60;
+        const HOUR: u64 = 60 * MINUTE;
+        const DAY: u64 = 24 * HOUR;
+
+        if secs < 45 {
+            (0, RelativeUnit::Now)
+        } else if secs < HOUR {
+            ((secs / MINUTE).max(1), RelativeUnit::Minute)
+        } else if secs < DAY {
+            (secs / HOUR, RelativeUnit::Hour)
+        } else if secs < 30 * DAY {
+            (secs / DAY, RelativeUnit::Day)
+        } else if secs < 365 * DAY {
+            (secs / (30 * DAY), RelativeUnit::Month)
+        } else {
+            (secs / (365 * DAY), RelativeUnit::Year)
+        }
+    }
+
+    fn format_en(&self, count: u64, past: bool) -> String {
+        let unit = match self {
+            RelativeUnit::Now => return "just now".to_string(),
+            RelativeUnit::Minute => "minute",
+            RelativeUnit::Hour => "hour",
+            RelativeUnit::Day => "day",
+            RelativeUnit::Month => "month",
+            RelativeUnit::Year => "year",
+        };
+        let plural = if count == 1 { "" } else { "s" };
+        if past {
+            format!("{} {}{} ago", count, unit, plural)
+        } else {
+            format!("in {} {}{}", count, unit, plural)
+        }
+    }
+
+    fn format_ja(&self, count: u64, past: bool) -> String {
+        let unit = match self {
+            RelativeUnit::Now => return "たった今".to_string(),
+            RelativeUnit::Minute => "分",
+            RelativeUnit::Hour => "時間",
+            RelativeUnit::Day => "日",
+            RelativeUnit::Month => "か月",
+            RelativeUnit::Year => "年",
+        };
+        format!("{}{}{}", count, unit, if past { "前" } else { "後" })
+    }
+
+    fn format_ar(&self, count: u64, past: bool) -> String {
+        // singular, dual, plural (3-10), singular accusative (11+)
+        let (singular, dual, plural, accusative) = match self {
+            RelativeUnit::Now => return "الآن".to_string(),
+            RelativeUnit::Minute => ("دقيقة", "دقيقتين", "دقائق", "دقيقة"),
+            RelativeUnit::Hour => ("ساعة", "ساعتين", "ساعات", "ساعة"),
+            RelativeUnit::Day => ("يوم", "يومين", "أيام", "يومًا"),
+            RelativeUnit::Month => ("شهر", "شهرين", "أشهر", "شهرًا"),
+            RelativeUnit::Year => ("سنة", "سنتين", "سنوات", "سنة"),
+        };
+        let amount = match count {
+            1 => singular.to_string(),
+            2 => dual.to_string(),
+            3..=10 => format!("{} {}", count, plural),
+            _ => format!("{} {}", count, accusative),
+        };
+        format!("{} {}", if past { "منذ" } else { "بعد" }, amount)
+    }
+}
@@ -0,0 +1,270 @@
+//! Privacy-preserving page view counts.
+//! No IP addresses, cookies or other identifiers are kept; only the number
+//! of views per post per day, persisted to an embedded database.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use axum::http::HeaderMap;
+
+use chrono::NaiveDate;
+
+use redb::{Database, ReadableTable, TableDefinition};
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::unix_time::UnixTime;
+
+
+// (date, post uuid) -> views, dates as YYYY-MM-DD in server_timezone
+const VIEWS_TABLE: TableDefinition<(&str, &str), u64> = TableDefinition::new("post_views");
+
+const DEFAULT_DATABASE_PATH: &str = "views.redb";
+
+/// Views are kept in memory for at most this time before written out.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Most distinct posts counted per day; views of other posts are dropped after that.
+const MAX_POSTS_PER_DAY: usize = 10000;
+
+/// Lowercase substrings of user agents not counted as views.
+const BOT_MARKERS: [&str; 16] = [
+    "bot",
+    "crawl",
+    "spider",
+    "slurp",
+    "archiver",
+    "fetcher",
+    "preview",
+    "monitor",
+    "headless",
+    "lighthouse",
+    "facebookexternalhit",
+    "curl/",
+    "wget/",
+    "python-",
+    "go-http-client",
+    "okhttp",
+];
+
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyViews {
+    pub date: String,
+    pub post_uuid: String,
+    pub views: u64,
+}
+
+/// Views counted in memory and periodically added to the database.
+/// Without a database, counts are kept in memory only.
+#[derive(Debug)]
+pub struct ViewCounter {
+    database: Option<Database>,
+    pending: Mutex<HashMap<(String, String), u64>>,
+    posts_on_date: Mutex<(String, HashSet<String>)>, // distinct posts counted on the latest date
+}
+
+impl ViewCounter {
+    fn open(path: &str) -> Result<Database, anyhow::Error> {
+        let database = Database::create(path)?;
+        let transaction = database.begin_write()?;
+        transaction.open_table(VIEWS_TABLE)?;
+        transaction.commit()?;
+        Ok(database)
+    }
+
+    pub fn new(path: &str) -> Self {
+        let database = match Self::open(path) {
+            Ok(database) => Some(database),
+            Err(e) => {
+                log::warn!("Failed to open view count database {}, counting in memory: {}", path, e);
+                None
+            },
+        };
+        Self {
+            database,
+            pending: Mutex::new(HashMap::new()),
+            posts_on_date: Mutex::new((String::new(), HashSet::new())),
+        }
+    }
+
+    /// Posts with views stored in the database on the date.
+    fn stored_posts(&self, date: &str) -> Result<HashSet<String>, anyhow::Error> {
+        let mut posts = HashSet::new();
+        if let Some(database) = &self.database {
+            let transaction = database.begin_read()?;
+            let table = transaction.open_table(VIEWS_TABLE)?;
+            for entry in table.range((date, "")..)? {
+                let (key, _) = entry?;
+                let (entry_date, post_uuid) = key.value();
+                if entry_date != date {
+                    break;
+                }
+                posts.insert(post_uuid.to_string());
+            }
+        }
+        Ok(posts)
+    }
+
+    /// Counts a view, unless `MAX_POSTS_PER_DAY` other posts were already counted on the date.
+    /// Blocks to read the posts stored on the date at the first view of each date.
+    pub fn record(&self, date: NaiveDate, post_uuid: &str) {
+        let date = date.format("%Y-%m-%d").to_string();
+        {
+            let mut posts_on_date = self.posts_on_date.lock().unwrap();
+            if posts_on_date.0 != date {
+                let posts = self.stored_posts(&date).unwrap_or_else(|e| {
+                    log::warn!("Failed to read view counts: {}", e);
+                    HashSet::new()
+                });
+                *posts_on_date = (date.clone(), posts);
+            }
+            let posts = &mut posts_on_date.1;
+            if !posts.contains(post_uuid) {
+                if posts.len() >= MAX_POSTS_PER_DAY {
+                    return;
+                }
+                posts.insert(post_uuid.to_string());
+            }
+        }
+        let key = (date, post_uuid.to_string());
+        *self.pending.lock().unwrap().entry(key).or_default() += 1;
+    }
+
+    /// Adds the views counted in memory to the database.
+    pub fn flush(&self) -> Result<(), anyhow::Error> {
+        let database = if let Some(database) = &self.database {
+            database
+        } else {
+            return Ok(());
+        };
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let result = (|| -> Result<(), anyhow::Error> {
+            let transaction = database.begin_write()?;
+            {
+                let mut table = transaction.open_table(VIEWS_TABLE)?;
+                for ((date, post_uuid), views) in &pending {
+                    let key = (date.as_str(), post_uuid.as_str());
+                    let total = table.get(&key)?.map(|value| value.value()).unwrap_or(0) + views;
+                    table.insert(&key, &total)?;
+                }
+            }
+            transaction.commit()?;
+            Ok(())
+        })();
+
+        if result.is_err() {
+            // keep the views for the next attempt
+            let mut current = self.pending.lock().unwrap();
+            for (key, views) in pending {
+                *current.entry(key).or_default() += views;
+            }
+        }
+        result
+    }
+
+    /// Views per post per day between the dates, inclusive, by date.
+    pub fn daily_views(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyViews>, anyhow::Error> {
+        let from = from.format("%Y-%m-%d").to_string();
+        let to = to.format("%Y-%m-%d").to_string();
+        let mut views: HashMap<(String, String), u64> = HashMap::new();
+
+        if let Some(database) = &self.database {
+            let transaction = database.begin_read()?;
+            let table = transaction.open_table(VIEWS_TABLE)?;
+            for entry in table.range((from.as_str(), "")..)? {
+                let (key, value) = entry?;
+                let (date, post_uuid) = key.value();
+                if date > to.as_str() {
+                    break;
+                }
+                views.insert((date.to_string(), post_uuid.to_string()), value.value());
+            }
+        }
+
+        for ((date, post_uuid), count) in self.pending.lock().unwrap().iter() {
+            if *date >= from && *date <= to {
+                *views.entry((date.clone(), post_uuid.clone())).or_default() += count;
+            }
+        }
+
+        let mut views: Vec<DailyViews> = views.into_iter()
+            .map(|((date, post_uuid), views)| DailyViews { date, post_uuid, views })
+            .collect();
+        views.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.post_uuid.cmp(&b.post_uuid)));
+        Ok(views)
+    }
+
+    /// Post uuids with the most views between the dates, with the views.
+    pub fn popular(&self, from: NaiveDate, to: NaiveDate, limit: usize) -> Result<Vec<(String, u64)>, anyhow::Error> {
+        let mut totals: HashMap<String, u64> = HashMap::new();
+        for daily in self.daily_views(from, to)? {
+            *totals.entry(daily.post_uuid).or_default() += daily.views;
+        }
+        let mut totals: Vec<(String, u64)> = totals.into_iter().collect();
+        totals.sort_by(|(a_uuid, a_views), (b_uuid, b_views)| b_views.cmp(a_views).then_with(|| a_uuid.cmp(b_uuid)));
+        totals.truncate(limit);
+        Ok(totals)
+    }
+}
+
+static VIEW_COUNTER: OnceLock<ViewCounter> = OnceLock::new();
+
+/// The database path is read from `VIEW_COUNT_DB`.
+pub fn view_counter() -> &'static ViewCounter {
+    VIEW_COUNTER.get_or_init(|| {
+        let path = env::var("VIEW_COUNT_DB").unwrap_or_default();
+        ViewCounter::new(if path.is_empty() { DEFAULT_DATABASE_PATH } else { &path })
+    })
+}
+
+pub fn is_bot(user_agent: &str) -> bool {
+    let user_agent = user_agent.to_lowercase();
+    user_agent.is_empty() || BOT_MARKERS.iter().any(|marker| user_agent.contains(marker))
+}
+
+/// Whether the request looks like a person reading, not a bot or a prefetch.
+pub fn is_countable(headers: &HeaderMap) -> bool {
+    let user_agent = headers.get("user-agent").and_then(|value| value.to_str().ok()).unwrap_or("");
+    let is_prefetch = ["purpose", "sec-purpose", "x-moz"].iter().any(|name| {
+        headers.get(*name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.contains("prefetch") || value.contains("prerender"))
+            .unwrap_or(false)
+    });
+    !is_bot(user_agent) && !is_prefetch
+}
+
+/// Today in server_timezone.
+pub fn today(config: &Config) -> NaiveDate {
+    UnixTime::now().to_datetime(config.server_timezone()).date_naive()
+}
+
+/// Counts a view of the post today, unless the request is not countable.
+/// Counting may open or read the database, so it runs on a blocking thread without being waited for.
+pub fn record_view(post_uuid: &str, headers: &HeaderMap, config: &Config) {
+    if is_countable(headers) {
+        let date = today(config);
+        let post_uuid = post_uuid.to_string();
+        tokio::task::spawn_blocking(move || view_counter().record(date, &post_uuid));
+    }
+}
+
+/// Writes out counted views periodically. Runs forever.
+pub async fn run_flusher() {
+    loop {
+        tokio::time::sleep(FLUSH_INTERVAL).await;
+        match tokio::task::spawn_blocking(|| view_counter().flush()).await {
+            Ok(Ok(())) => {},
+            Ok(Err(e)) => log::warn!("Failed to write view counts: {}", e),
+            Err(e) => log::warn!("Failed to write view counts: {}", e),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+
+import { SiteConfig } from "./site-config";
+
+/**
+ * Config is refetched at most this often, so that scheduled announcements
+ * show up and expire without a page load.
+ */
+const CONFIG_MAX_AGE = 60 * 1000; // milliseconds
+
+let configPromise: Promise<SiteConfig> | undefined;
+let configFetchedAt = 0;
+
+const getConfig = (): Promise<SiteConfig> => {
+    const now = Date.now();
+    if (configPromise === undefined || now - configFetchedAt > CONFIG_MAX_AGE) {
+        configFetchedAt = now;
+        configPromise = alarkhabil.frontendApi.getConfig().catch((e) => {
+            console.warn(e);
+            return SiteConfig.INITIAL;
+        });
+    }
+    return configPromise;
+};
+
+/**
+ * Renders the announcements active on the page, as the server does.
+ * @param pathname Path of the page
+ */
+export const updateAnnouncements = async (pathname: string) => {
+    const container = document.querySelector<HTMLElement>('#announcements');
+    if (!container) {
+        return;
+    }
+    const config = await getConfig();
+    const now = Date.now() / 1000;
+    container.textContent = '';
+    for (const announcement of config.announcements) {
+        if (announcement.start_timestamp != null && now < announcement.start_timestamp) {
+            continue;
+        }
+        if (announcement.end_timestamp != null && announcement.end_timestamp <= now) {
+            continue;
+        }
+        if (announcement.routes.length > 0 && !announcement.routes.some((prefix) => pathname.startsWith(prefix))) {
+            continue;
+        }
+        const element = document.createElement('div');
+        element.classList.add('announcement', `announcement-${announcement.severity}`);
+        element.setAttribute('role', announcement.severity == 'critical' ? 'alert' : 'status');
+        element.innerHTML = announcement.html; // rendered by the server from config
+        container.appendChild(element);
+    }
+};
\ No newline at end of file
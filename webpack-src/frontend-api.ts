@@ -5,8 +5,18 @@ import { SiteConfig } from "./site-config";
 
 
 export interface TimestampFormatResult {
+    readonly timestamp: number; // unix timestamp in seconds
     readonly datetime: string; // <time datetime="...">...</time>
     readonly formatted: string; // for display
+    readonly formatted_alt: string; // in the secondary calendar for calendar_lang, or empty
+}
+
+export interface TimestampFormatOptions {
+    readonly timezone?: string; // IANA timezone name, defaults to server_timezone
+    readonly locale?: string; // BCP 47 language tag
+    readonly pattern?: string; // 'default', 'date', 'time', 'iso8601', 'rfc2822', 'locale_date', 'locale_datetime' or strftime format
+    readonly relative?: boolean; // e.g. '3 days ago'
+    readonly calendar_lang?: string; // language of the content, selects the secondary calendar
 }
 
 export class FrontendApi {
@@ -39,13 +49,50 @@ export class FrontendApi {
         return result.data.html;
     }
 
-    public async formatTimestampInSeconds(seconds: number): Promise<TimestampFormatResult> {
+    public async formatTimestampInSeconds(seconds: number, options: TimestampFormatOptions = {}): Promise<TimestampFormatResult> {
         const params = new URLSearchParams();
         params.set('timestamp', seconds.toString());
+        if (options.timezone !== undefined) {
+            params.set('timezone', options.timezone);
+        }
+        if (options.locale !== undefined) {
+            params.set('locale', options.locale);
+        }
+        if (options.pattern !== undefined) {
+            params.set('pattern', options.pattern);
+        }
+        if (options.relative !== undefined) {
+            params.set('relative', String(options.relative));
+        }
+        if (options.calendar_lang !== undefined) {
+            params.set('calendar_lang', options.calendar_lang);
+        }
         const result = await this.v1.get<TimestampFormatResult>('timestamp/format', params);
         if (!result.ok) {
             throw new Error(`Failed to format timestamp: ${result.status}`);
         }
         return result.data;
     }
+
+    public async formatTimestampsInSeconds(seconds: number[], options: TimestampFormatOptions = {}): Promise<TimestampFormatResult[]> {
+        const result = await this.v1.post<
+            { timestamps: number[] } & TimestampFormatOptions,
+            { results: TimestampFormatResult[] }
+        >('timestamp/format', { timestamps: seconds, ...options });
+        if (!result.ok) {
+            throw new Error(`Failed to format timestamps: ${result.status}`);
+        }
+        return result.data.results;
+    }
+
+    /**
+     * Counts a view of a post rendered without a page load. No identifier is sent.
+     * @param postUuid UUID of the viewed post
+     */
+    public async recordView(postUuid: string): Promise<void> {
+        const result = await this.v1.post<{ post_uuid: string }, {}>('views/record', { post_uuid: postUuid });
+        if (!result.ok) {
+            throw new Error(`Failed to record view: ${result.status}`);
+        }
+    }
 }
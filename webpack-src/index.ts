@@ -1,19 +1,11 @@
 
 import './alarkhabil';
-import { routerBuilder } from './routes-common';
+import { routerBuilder, JS_HANDLED_PATHNAMES } from './routes-common';
 import './routes';
 
 
 const router = routerBuilder.build();
 
-// routes not prerendered by the server
-const JS_HANDLED_PATHNAMES: string[] = [
-    '/invites/',
-    '/signup/',
-    '/signin/',
-    '/account/',
-];
-
 const navigate = (url: string) => {
     const parsedUrl = new URL(url, document.location.href);
     if (parsedUrl.origin !== document.location.origin) {
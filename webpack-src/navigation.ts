@@ -0,0 +1,26 @@
+const NAVIGATION_SELECTOR = '#topbar-navigation, #global-footer-navigation';
+
+/**
+ * Marks the navigation links to the page as current, as the server does.
+ */
+export const updateNavigation = (pathname: string) => {
+    for (const navigation of document.querySelectorAll(NAVIGATION_SELECTOR)) {
+        for (const link of navigation.querySelectorAll('a, .navigation-heading')) {
+            link.removeAttribute('aria-current');
+        }
+        for (const link of navigation.querySelectorAll<HTMLAnchorElement>('a[href]')) {
+            if (link.classList.contains('navigation-external')) continue;
+            if (new URL(link.href, document.location.href).pathname != pathname) continue;
+            link.setAttribute('aria-current', 'page');
+            // every enclosing submenu's parent item, to any depth
+            let submenu = link.closest('.navigation-submenu');
+            while (submenu) {
+                const parent = submenu.parentElement?.firstElementChild;
+                if (parent && parent != link && !parent.hasAttribute('aria-current')) {
+                    parent.setAttribute('aria-current', 'true');
+                }
+                submenu = submenu.parentElement?.closest('.navigation-submenu') ?? null;
+            }
+        }
+    }
+};
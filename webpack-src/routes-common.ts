@@ -3,9 +3,19 @@ import { Router, RouteHandler, ErrorRouteHandler, RouteParams } from './router';
 import { PageMetadata } from './page-metadata';
 import { instantiateTemplate, content } from "./render";
 import './alarkhabil';
+import { updateAnnouncements } from './announcements';
+import { updateNavigation } from './navigation';
 
 export const routerBuilder: Router.Builder = new Router.Builder();
 
+// routes not prerendered by the server
+export const JS_HANDLED_PATHNAMES: string[] = [
+    '/invites/',
+    '/signup/',
+    '/signin/',
+    '/account/',
+];
+
 export const commonHandler = (params: RouteParams, isError: boolean, title = '') => {
     if (isError) {
         PageMetadata.allowRobots = false; // Disallow robots from indexing this page. (404 alternative)
@@ -20,6 +30,9 @@ export const commonHandler = (params: RouteParams, isError: boolean, title = '')
     const url = new URL(params.matchedPath, document.location.href);
     PageMetadata.ogUrl = url.href;
     PageMetadata.ogTitle = ogTitle;
+
+    updateAnnouncements(url.pathname).catch((e) => console.warn(e));
+    updateNavigation(url.pathname);
 };
 
 export const handlerNotFound: RouteHandler = (params) => {
@@ -35,5 +48,17 @@ export const errorHandler: ErrorRouteHandler = (params, error) => {
     content.appendChild(document.createElement('pre')).textContent = String(error);
 };
 
-routerBuilder.setFallbackHandler(handlerNotFound);
+/**
+ * Pages without a route here (language pages, series, vanity routes, local pages and so on)
+ * are rendered by the server.
+ */
+export const handlerServerRendered: RouteHandler = (params) => {
+    if (JS_HANDLED_PATHNAMES.includes(params.matchedPath)) {
+        handlerNotFound(params);
+        return;
+    }
+    location.reload();
+};
+
+routerBuilder.setFallbackHandler(handlerServerRendered);
 routerBuilder.setErrorHandler(errorHandler);
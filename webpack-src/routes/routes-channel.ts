@@ -4,28 +4,11 @@ import { instantiateTemplate, content, parseHTML } from "../render";
 import { DnsToken } from "../dns-token";
 import { Uuid } from "../uuid";
 import { TimestampFormatResult } from "../frontend-api";
+import { detectDirection, directionFromText } from "../text-direction";
 
-routerBuilder.add('/c/', async (routeParams) => {
-    const channels = await alarkhabil.backendApi.channel.list();
-
-    commonHandler(routeParams, false, 'Channels');
-    content.textContent = '';
-    const postListElement = instantiateTemplate('template-content-post-list', content);
-    postListElement.querySelector('.post-list-title')!.textContent = 'Channels';
-    const postListItemsElement = postListElement.querySelector<HTMLElement>('.post-list-items')!;
-    if (channels.length == 0) {
-        const paragraph = instantiateTemplate('template-content-single-paragraph-message', postListItemsElement);
-        paragraph.textContent = 'There is no channel in this list.';
-        return;
-    }
-    for (const channel of channels) {
-        const channelElement = instantiateTemplate('template-content-channel-list-item', postListItemsElement);
-        channelElement.lang = channel.lang;
-        channelElement.querySelector<HTMLAnchorElement>('.channel-name-link')!.href = `/c/${channel.handle}/`;
-        channelElement.querySelector<HTMLAnchorElement>('.channel-handle-link')!.href = `/c/${channel.handle}/`;
-        channelElement.querySelector<HTMLElement>('.channel-name')!.textContent = channel.name;
-        channelElement.querySelector<HTMLElement>('.channel-handle-value')!.textContent = channel.handle;
-    }
+// The channel list is rendered by the server, with the language navigation and alternate links.
+routerBuilder.add('/c/', async (_routeParams) => {
+    location.reload();
 });
 
 routerBuilder.add('/c/:channelHandle/', async (routeParams) => {
@@ -33,6 +16,7 @@ routerBuilder.add('/c/:channelHandle/', async (routeParams) => {
     const channel = await alarkhabil.backendApi.channel.getByHandle(channelHandle);
     const channelUuid = channel.uuid;
     const posts = await alarkhabil.backendApi.post.listByChannel(channelUuid);
+    const authors = await alarkhabil.backendApi.author.listByChannel(channelUuid);
     const channelDescriptionHtml = await alarkhabil.frontendApi.parseMarkdown(channel.descriptionText);
     const timestampMap = new Map<number, TimestampFormatResult>();
     for (const post of posts) {
@@ -40,7 +24,7 @@ routerBuilder.add('/c/:channelHandle/', async (routeParams) => {
         if (timestampMap.has(timestampInSeconds)) {
             continue;
         }
-        const timestamp = await alarkhabil.frontendApi.formatTimestampInSeconds(timestampInSeconds);
+        const timestamp = await alarkhabil.frontendApi.formatTimestampInSeconds(timestampInSeconds, { calendar_lang: channel.lang });
         timestampMap.set(timestampInSeconds, timestamp);
     }
     const timestamp = await alarkhabil.frontendApi.formatTimestampInSeconds(channel.createdDate);
@@ -50,6 +34,7 @@ routerBuilder.add('/c/:channelHandle/', async (routeParams) => {
     content.textContent = '';
     const channelElement = instantiateTemplate('template-content-channel', content);
     channelElement.lang = channel.lang;
+    channelElement.dir = detectDirection(channel.lang, channel.name);
     channelElement.querySelector<HTMLElement>('.breadcrumbs-channel-handle')!.textContent = channel.handle;
     channelElement.querySelector<HTMLElement>('.channel-name')!.textContent = channel.name;
     channelElement.querySelector<HTMLElement>('.channel-handle-value')!.textContent = channel.handle;
@@ -57,6 +42,17 @@ routerBuilder.add('/c/:channelHandle/', async (routeParams) => {
     channelDateTime.dateTime = timestamp.datetime;
     channelDateTime.textContent = timestamp.formatted;
     channelElement.querySelector<HTMLElement>('.channel-description')!.appendChild(parseHTML(channelDescriptionHtml));
+    channelElement.querySelector<HTMLAnchorElement>('.channel-archive-link')!.href = `/c/${channel.handle}/archive/`;
+    channelElement.querySelector<HTMLAnchorElement>('.channel-stats-link')!.href = `/c/${channel.handle}/stats/`;
+    channelElement.querySelector<HTMLAnchorElement>('.channel-epub-link')!.href = `/c/${channel.handle}/export.epub`;
+    const channelContributorsElement = channelElement.querySelector<HTMLElement>('.channel-contributors-items')!;
+    for (const author of authors) {
+        const postCount = posts.filter((post) => post.author?.uuid == author.uuid).length;
+        const contributorElement = instantiateTemplate('template-content-channel-contributor', channelContributorsElement);
+        contributorElement.querySelector<HTMLAnchorElement>('.channel-contributor-link')!.href = `/authors/${author.uuid}/`;
+        contributorElement.querySelector<HTMLElement>('.author-name')!.textContent = author.name;
+        contributorElement.querySelector<HTMLElement>('.channel-contributor-post-count')!.textContent = `${postCount} ${postCount == 1 ? 'post' : 'posts'}`;
+    }
     const channelPostsElement = channelElement.querySelector<HTMLElement>('.channel-posts-items')!;
     if (posts.length == 0) {
         const paragraph = instantiateTemplate('template-content-single-paragraph-message', channelPostsElement);
@@ -66,6 +62,7 @@ routerBuilder.add('/c/:channelHandle/', async (routeParams) => {
     for (const post of posts) {
         const postElement = instantiateTemplate('template-content-post-list-item', channelPostsElement);
         postElement.lang = channel.lang;
+        postElement.dir = detectDirection(channel.lang, post.title);
         postElement.querySelector<HTMLAnchorElement>('.post-channel-name-link')!.href = `/c/${channel!.handle}/`;
         postElement.querySelector<HTMLAnchorElement>('.post-channel-handle-link')!.href = `/c/${channel!.handle}/`;
         postElement.querySelector<HTMLElement>('.post-channel-name')!.textContent = channel!.name;
@@ -76,6 +73,9 @@ routerBuilder.add('/c/:channelHandle/', async (routeParams) => {
         const timestamp = timestampMap.get(post.revisionDate)!;
         postDateTimeElement.dateTime = timestamp.datetime;
         postDateTimeElement.textContent = timestamp.formatted;
+        const postDateAltElement = postElement.querySelector<HTMLElement>('.post-date-alt')!;
+        postDateAltElement.textContent = timestamp.formatted_alt;
+        postDateAltElement.hidden = timestamp.formatted_alt == '';
         postElement.querySelector<HTMLAnchorElement>('.post-author-link')!.href = `/authors/${post.author!.uuid}/`;
         postElement.querySelector<HTMLElement>('.post-author-name')!.textContent = post.author!.name;
         postElement.querySelector<HTMLElement>('.post-author-uuid')!.textContent = post.author!.uuid;
@@ -86,21 +86,33 @@ routerBuilder.add('/c/:channelHandle/:postUuid/', async (routeParams) => {
     const channelHandle = DnsToken(routeParams.placeholders.get('channelHandle')!);
     const postUuid = Uuid(routeParams.placeholders.get('postUuid')!);
     const post = await alarkhabil.backendApi.post.get(postUuid);
-    const timestamp = await alarkhabil.frontendApi.formatTimestampInSeconds(post.revisionDate);
+    const timestamp = await alarkhabil.frontendApi.formatTimestampInSeconds(post.revisionDate, { calendar_lang: post.channel!.lang });
+    const citationDate = await alarkhabil.frontendApi.formatTimestampInSeconds(post.revisionDate, { pattern: '%Y-%m-%d' });
     const html = await alarkhabil.frontendApi.parseMarkdown(post.revisionText);
+    alarkhabil.frontendApi.recordView(postUuid).catch((e) => console.warn(e));
 
     commonHandler(routeParams, false, post.title);
     content.textContent = '';
     const postElement = instantiateTemplate('template-content-post', content);
     postElement.lang = post.channel!.lang;
+    const postDirection = detectDirection(post.channel!.lang, post.title);
+    postElement.dir = postDirection;
     postElement.querySelector<HTMLAnchorElement>('.post-channel-name-link')!.href = `/c/${post.channel!.handle}/`;
     postElement.querySelector<HTMLAnchorElement>('.post-channel-handle-link')!.href = `/c/${post.channel!.handle}/`;
     postElement.querySelector<HTMLElement>('.post-channel-name')!.textContent = post.channel!.name;
     postElement.querySelector<HTMLElement>('.post-channel-handle')!.textContent = post.channel!.handle;
-    postElement.querySelector<HTMLElement>('.post-title')!.textContent = post.title;
+    const postTitleElement = postElement.querySelector<HTMLElement>('.post-title')!;
+    postTitleElement.dir = directionFromText(post.title) ?? postDirection;
+    postTitleElement.textContent = post.title;
     const postDateTimeElement = postElement.querySelector<HTMLTimeElement>('.post-date-time')!;
     postDateTimeElement.dateTime = timestamp.datetime;
     postDateTimeElement.textContent = timestamp.formatted;
+    const postDateAltElement = postElement.querySelector<HTMLElement>('.post-date-alt')!;
+    postDateAltElement.textContent = timestamp.formatted_alt;
+    postDateAltElement.hidden = timestamp.formatted_alt == '';
+    postElement.querySelector<HTMLAnchorElement>('.post-revisions-link')!.href = `/c/${post.channel!.handle}/${postUuid}/revisions/`;
+    postElement.querySelector<HTMLAnchorElement>('.post-markdown-link')!.href = `/c/${post.channel!.handle}/${postUuid}/index.md`;
+    postElement.querySelector<HTMLAnchorElement>('.post-plain-text-link')!.href = `/c/${post.channel!.handle}/${postUuid}/index.txt`;
     postElement.querySelector<HTMLAnchorElement>('.post-author-link')!.href = `/authors/${post.author!.uuid}/`;
     postElement.querySelector<HTMLElement>('.post-author-name')!.textContent = post.author!.name;
     postElement.querySelector<HTMLElement>('.post-author-uuid')!.textContent = post.author!.uuid;
@@ -113,4 +125,72 @@ routerBuilder.add('/c/:channelHandle/:postUuid/', async (routeParams) => {
     }
     const postBodyElement = postElement.querySelector<HTMLElement>('.post-body')!;
     postBodyElement.appendChild(parseHTML(html));
+
+    // same as Citation::to_text on the server
+    const postUrl = `/c/${post.channel!.handle}/${postUuid}/`;
+    const permalink = new URL(postUrl, alarkhabil.siteConfig.top_url).href;
+    const citation = `${post.author!.name} (${citationDate.formatted}). ${post.title}. ${post.channel!.name}, ${alarkhabil.siteConfig.site_name}. ${permalink}`;
+    postElement.querySelector<HTMLElement>('.post-cite-text')!.textContent = citation;
+    postElement.querySelector<HTMLAnchorElement>('.post-cite-bibtex-link')!.href = `${postUrl}cite.bib`;
+    postElement.querySelector<HTMLAnchorElement>('.post-cite-ris-link')!.href = `${postUrl}cite.ris`;
+    postElement.querySelector<HTMLAnchorElement>('.post-cite-csl-json-link')!.href = `${postUrl}cite.json`;
+    postElement.querySelector<HTMLElement>('.post-cite')!.hidden = false;
+});
+
+// Archives are rendered by the server.
+routerBuilder.add('/c/:channelHandle/archive/', async (_routeParams) => {
+    location.reload();
+});
+
+routerBuilder.add('/c/:channelHandle/archive/:year/', async (_routeParams) => {
+    location.reload();
+});
+
+routerBuilder.add('/c/:channelHandle/archive/:year/:month/', async (_routeParams) => {
+    location.reload();
+});
+
+// Statistics are rendered by the server.
+routerBuilder.add('/c/:channelHandle/stats/', async (_routeParams) => {
+    location.reload();
+});
+
+// Revisions are rendered by the server.
+routerBuilder.add('/c/:channelHandle/:postUuid/revisions/', async (_routeParams) => {
+    location.reload();
+});
+
+routerBuilder.add('/c/:channelHandle/:postUuid/r/:revisionUuid/', async (_routeParams) => {
+    location.reload();
+});
+
+routerBuilder.add('/c/:channelHandle/:postUuid/diff/', async (_routeParams) => {
+    location.reload();
+});
+
+// Source text, served by the server as is.
+routerBuilder.add('/c/:channelHandle/:postUuid/index.md', async (_routeParams) => {
+    location.reload();
+});
+
+routerBuilder.add('/c/:channelHandle/:postUuid/index.txt', async (_routeParams) => {
+    location.reload();
+});
+
+// Citations, served by the server.
+routerBuilder.add('/c/:channelHandle/:postUuid/cite.bib', async (_routeParams) => {
+    location.reload();
+});
+
+routerBuilder.add('/c/:channelHandle/:postUuid/cite.ris', async (_routeParams) => {
+    location.reload();
+});
+
+routerBuilder.add('/c/:channelHandle/:postUuid/cite.json', async (_routeParams) => {
+    location.reload();
+});
+
+// EPUB export, generated by the server.
+routerBuilder.add('/c/:channelHandle/export.epub', async (_routeParams) => {
+    location.reload();
 });
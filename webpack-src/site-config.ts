@@ -1,14 +1,54 @@
 
 import { deepFreeze } from "./freeze";
 
+export type NavigationSource =
+    | { readonly type: 'meta_pages' }
+    | { readonly type: 'channels'; readonly limit?: number | null };
+
 export interface NavigationItem {
-    readonly url: string;
-    readonly text: string;
+    readonly url?: string;
+    readonly text?: string;
+    readonly source?: NavigationSource;
+    readonly children?: NavigationItem[];
+}
+
+export interface SeriesDefinition {
+    readonly name: string;
+    readonly title: string;
+    readonly posts: string[]; // post uuids
+}
+
+export interface Announcement {
+    readonly text: string; // Markdown
+    readonly severity: 'info' | 'warning' | 'critical';
+    readonly start: string; // in server_timezone unless with an offset; empty for no start
+    readonly end: string; // exclusive; empty for no end
+    readonly routes: string[]; // path prefixes; empty for every page
+    readonly start_timestamp: number | null; // unix timestamp in seconds
+    readonly end_timestamp: number | null;
+    readonly html: string; // rendered text
+}
+
+export interface RedirectRule {
+    readonly kind: 'exact' | 'prefix' | 'regex';
+    readonly from: string;
+    readonly to: string;
+    readonly status: 301 | 302;
 }
 
+export type HomeSection =
+    | { readonly type: 'featured'; readonly title?: string; readonly posts: string[] }
+    | { readonly type: 'latest'; readonly title?: string; readonly limit?: number }
+    | { readonly type: 'popular'; readonly limit?: number }
+    | { readonly type: 'channel'; readonly title?: string; readonly handle: string; readonly limit?: number }
+    | { readonly type: 'tag'; readonly title?: string; readonly tag_name: string; readonly limit?: number }
+    | { readonly type: 'channel_directory'; readonly title?: string }
+    | { readonly type: 'meta_page'; readonly page_name: string };
+
 export interface SiteConfig {
     readonly api_url: string;
     readonly site_name: string;
+    readonly site_lang: string;
     readonly site_description: string;
     readonly site_copyright: string;
     readonly header_navigation: NavigationItem[];
@@ -16,6 +56,16 @@ export interface SiteConfig {
     readonly top_url: string;
     readonly og_image: string;
     readonly server_timezone: string;
+    readonly secondary_calendars: { readonly [langRange: string]: 'gregorian' | 'japanese' | 'islamic' };
+    readonly search_refresh_interval: number; // seconds
+    readonly related_posts_count: number;
+    readonly tag_list_min_page_count: number;
+    readonly series_tag_prefix: string;
+    readonly series: SeriesDefinition[];
+    readonly home_sections: HomeSection[];
+    readonly announcements: Announcement[];
+    readonly redirects: RedirectRule[];
+    readonly meta_page_routes: { readonly [path: string]: string }; // path -> meta page name
 }
 
 export namespace SiteConfig {
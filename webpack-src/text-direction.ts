@@ -0,0 +1,59 @@
+
+export type TextDirection = 'ltr' | 'rtl' | 'auto';
+
+// scripts written from right to left (ISO 15924)
+const RTL_SCRIPTS = new Set([
+    'adlm', 'arab', 'aran', 'hebr', 'mand', 'mend', 'nkoo',
+    'rohg', 'samr', 'syrc', 'thaa', 'yezi',
+]);
+
+// languages whose default script is written from right to left
+const RTL_LANGUAGES = new Set([
+    'ar', 'arc', 'ckb', 'dv', 'fa', 'glk', 'he', 'iw', 'ji', 'ks',
+    'lrc', 'mzn', 'pnb', 'ps', 'sd', 'syr', 'ug', 'ur', 'yi',
+]);
+
+// primary subtags that do not denote a particular language
+const NON_LANGUAGES = new Set(['und', 'mul', 'zxx', 'mis']);
+
+const RTL_CHAR = /[\u0590-\u08FF\uFB1D-\uFDFF\uFE70-\uFEFE\u{10800}-\u{10FFF}\u{1E800}-\u{1EFFF}]/u;
+const LETTER = /\p{L}/u;
+
+/**
+ * Returns the direction implied by a BCP 47 language tag, if any.
+ */
+export const directionFromLang = (lang: string): TextDirection | undefined => {
+    const subtags = lang.trim().toLowerCase().split(/[-_]/);
+    const primary = subtags[0] ?? '';
+    if (!/^[a-z]{2,8}$/.test(primary)) {
+        return undefined;
+    }
+    for (const subtag of subtags.slice(1)) {
+        if (subtag.length == 1) {
+            break; // extensions
+        }
+        if (/^[a-z]{4}$/.test(subtag)) {
+            return RTL_SCRIPTS.has(subtag) ? 'rtl' : 'ltr';
+        }
+    }
+    if (NON_LANGUAGES.has(primary)) {
+        return undefined;
+    }
+    return RTL_LANGUAGES.has(primary) ? 'rtl' : 'ltr';
+};
+
+/**
+ * Returns the direction of the first strong (letter) character, if any.
+ */
+export const directionFromText = (text: string): TextDirection | undefined => {
+    for (const c of text) {
+        if (LETTER.test(c)) {
+            return RTL_CHAR.test(c) ? 'rtl' : 'ltr';
+        }
+    }
+    return undefined;
+};
+
+export const detectDirection = (lang: string, text: string): TextDirection => {
+    return directionFromLang(lang) ?? directionFromText(text) ?? 'auto';
+};